@@ -1,131 +1,583 @@
+#![allow(clippy::needless_return)]
+use floatflow::bounds::{Bound, BoundMode};
+use floatflow::constraint::{ConstrainedSolver, Constraint, ConstraintMethod};
 use floatflow::error::{FFError, Result};
 use floatflow::executor::Executor;
-use floatflow::graph::NodeAllocator;
-use floatflow::optimizer::{AdaGrad, Adam, Optimizer, SGD};
-use floatflow::polynomial::{Polynomial, Term};
+use floatflow::graph::{Graph, NodeAllocator};
+use floatflow::groebner::GroebnerBasis;
+use floatflow::lbfgs::LBFGS;
+use floatflow::least_squares::{LeastSquares, Method};
+use floatflow::loss::Loss;
+use floatflow::multistart::MultiStart;
+use floatflow::optimizer::{AdaGrad, Adam, GradClip, Optimizer, SGD};
+use floatflow::polynomial::{tranform_weighted_system_to_graph, MonomialOrder, Polynomial, Term};
+use floatflow::random::Sampling;
+use floatflow::resultant::solve_by_elimination;
+use floatflow::scaling::{EquationScaling, Scaling};
+use floatflow::scheduler::{Schedule, Scheduler};
+use floatflow::solver::Solver;
 use floatflow::store::Store;
 use std::collections::HashMap;
+use std::time::Duration;
 
 #[derive(Debug)]
 struct PolynomialConfig {
     multi_poly: Vec<Polynomial>,
     init: HashMap<String, f32>,
+    bounds: HashMap<String, Bound>,
+    weights: Vec<f32>,
+    constraints: Vec<Constraint>,
 }
 impl PolynomialConfig {
     pub fn new() -> Self {
         PolynomialConfig {
             multi_poly: Vec::new(),
             init: HashMap::new(),
+            bounds: HashMap::new(),
+            weights: Vec::new(),
+            constraints: Vec::new(),
         }
     }
-    pub fn parse(&mut self, string: &String) -> Result<()> {
-        for line in string.trim().lines() {
-            let words = line
-                .split('@')
-                .map(|s| String::from(s))
-                .collect::<Vec<String>>();
+    pub fn parse(&mut self, string: &str) -> Result<()> {
+        for (number, line) in string.trim().lines().enumerate() {
+            if line.trim().is_empty() || line.trim().starts_with('#') {
+                continue;
+            }
+            let located = |e| match e {
+                FFError::Parse(info) => FFError::Parse(format!("line {}, {}", number + 1, info)),
+                other => other,
+            };
+            // inequality constraints, e.g. `x^2 + y^2 <= 1` or `x >= 0`
+            if line.contains("<=") || line.contains(">=") {
+                self.constraints
+                    .push(Constraint::parse(line).map_err(located)?);
+                continue;
+            }
+            // equations in conventional notation, e.g. `x^2 + 3x*y = 1`
+            if !line.contains('@') {
+                self.multi_poly
+                    .push(Polynomial::parse(line).map_err(located)?);
+                continue;
+            }
+            let words = line.split('@').map(String::from).collect::<Vec<String>>();
             println!("{:?}", words);
             let mut iter = words.into_iter().peekable();
-            let token = iter
-                .peek()
-                .ok_or(FFError::Parse(format!("parse fiailed!!!")))?;
+            // split always yields at least one word
+            let token = iter.next().unwrap_or_default();
             if token == "i" {
-                //skip i
-                iter.next()
-                    .ok_or(FFError::Parse(format!("parse fiailed!!!")))?;
-                while let Some(_) = iter.peek() {
-                    //parse name
-                    let name = iter
-                        .next()
-                        .ok_or(FFError::Parse(format!("parse fiailed!!!")))?;
+                while let Some(name) = iter.next() {
                     let init = iter
                         .next()
-                        .ok_or(FFError::Parse(format!("parse fiailed!!!")))?
+                        .ok_or(FFError::Parse(format!("no initial value for {}", name)))
+                        .map_err(located)?
                         .parse::<f32>()?;
                     self.init.insert(name, init);
                 }
-                // parse_init(&mut iter)?
+            } else if token == "b" {
+                // b@name@lower@upper[@projection|softplus|sigmoid], use inf/-inf for open sides
+                let mut next = |what: &str| {
+                    return iter
+                        .next()
+                        .ok_or(FFError::Parse(format!(
+                            "b@name@lower@upper[@mode] is missing the {}",
+                            what
+                        )))
+                        .map_err(located);
+                };
+                let name = next("name")?;
+                let lower = next("lower bound")?.parse::<f32>()?;
+                let upper = next("upper bound")?.parse::<f32>()?;
+                let mode = match iter.next().as_deref() {
+                    None | Some("projection") => BoundMode::Projection,
+                    Some("softplus") => BoundMode::Softplus,
+                    Some("sigmoid") => BoundMode::Sigmoid,
+                    Some(other) => {
+                        return Err(located(FFError::Parse(format!(
+                            "unknown bound mode: {}",
+                            other
+                        ))))
+                    }
+                };
+                let bound = Bound::new(lower, upper).with_mode(mode);
+                bound.validate().map_err(located)?;
+                self.bounds.insert(name, bound);
+            } else if token == "w" {
+                // w@w1@w2..., one weight per equation in the order they appear
+                for weight in iter {
+                    self.weights.push(weight.parse::<f32>()?);
+                }
             } else if token == "c" {
                 let mut poly = Polynomial::new();
-                while let Some(_) = iter.peek() {
+                loop {
                     let mut t = Term::new();
-                    //skip c
-                    iter.next()
-                        .ok_or(FFError::Parse(format!("parse fiailed!!!")))?;
                     //parse coefficient
                     t.set_coefficient(
                         iter.next()
-                            .ok_or(FFError::Parse(format!("parse fiailed!!!")))?
+                            .ok_or(FFError::Parse("c@ without a coefficient".to_string()))
+                            .map_err(located)?
                             .parse::<f32>()?,
                     );
-                    if iter.peek().is_none() {
-                        println!("{:?}", t);
+                    //parse name
+                    let Some(name) = iter.next() else {
+                        println!("{}", t);
                         poly.add_term(t);
                         break;
-                    }
-                    //parse name
-                    let name = iter
-                        .next()
-                        .ok_or(FFError::Parse(format!("parse fiailed!!!")))?;
+                    };
                     //parse order
                     let order = iter
                         .next()
-                        .ok_or(FFError::Parse(format!("parse fiailed!!!")))?
+                        .ok_or(FFError::Parse(format!("no order for {}", name)))
+                        .map_err(located)?
                         .parse::<usize>()?;
                     t.add_variable(&name, order);
-                    println!("{:?}", t);
+                    println!("{}", t);
                     poly.add_term(t);
+                    //skip c
+                    if iter.next().is_none() {
+                        break;
+                    }
                 }
                 self.multi_poly.push(poly)
+            } else {
+                return Err(located(FFError::Parse(format!(
+                    "unknown line type: {}",
+                    token
+                ))));
+            }
+        }
+        return Ok(());
+    }
+}
+#[derive(Debug)]
+struct SolverConfig {
+    solver: String,
+    learning_rate: f32,
+    history: usize,
+    iterations: usize,
+    loss_tolerance: f32,
+    gradient_tolerance: f32,
+    parameter_tolerance: f32,
+    time_limit: Option<Duration>,
+    schedule: Option<Schedule>,
+    grad_clip: Option<GradClip>,
+    check_finite: bool,
+    square_free: bool,
+    starts: usize,
+    sampling: Sampling,
+    seed: u64,
+    scaling: Scaling,
+    loss: Loss,
+    constraint_method: ConstraintMethod,
+    path: Option<String>,
+}
+impl SolverConfig {
+    pub fn new() -> Self {
+        SolverConfig {
+            solver: "sgd".to_string(),
+            learning_rate: 0.01,
+            history: 8,
+            iterations: 200,
+            loss_tolerance: 1e-10,
+            gradient_tolerance: 0.0,
+            parameter_tolerance: 0.0,
+            time_limit: None,
+            schedule: None,
+            grad_clip: None,
+            check_finite: false,
+            square_free: false,
+            starts: 32,
+            sampling: Sampling::Sobol,
+            seed: 0,
+            scaling: Scaling::Unscaled,
+            loss: Loss::Squared,
+            constraint_method: ConstraintMethod::Penalty,
+            path: None,
+        }
+    }
+    // solve_poly [--solver sgd|adam|adagrad|lbfgs|newton|gauss-newton|lm|aberth|companion|homotopy|groebner|resultant|multistart]
+    //            [--lr F] [--history N] [--iterations N] [--schedule SPEC]
+    //            [--loss-tol F] [--grad-tol F] [--param-tol F] [--time-limit SECONDS]
+    //            [--clip-value F | --clip-norm F] [--check-finite] [--square-free]
+    //            [--starts N] [--sampling uniform|lhs|sobol] [--seed N]
+    //            [--scaling none|coefficients|initial]
+    //            [--loss squared|absolute|huber[:F]|logcosh[:F]|cauchy[:F]]
+    //            [--constraints penalty|barrier] [file]
+    pub fn parse_args(&mut self, args: &[String]) -> Result<()> {
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .ok_or(FFError::Parse(format!("missing value for {}", arg)))
+            };
+            match arg.as_str() {
+                "--solver" => self.solver = value()?.clone(),
+                "--lr" => self.learning_rate = value()?.parse::<f32>()?,
+                "--history" => self.history = value()?.parse::<usize>()?,
+                "--iterations" => self.iterations = value()?.parse::<usize>()?,
+                "--loss-tol" => self.loss_tolerance = value()?.parse::<f32>()?,
+                "--grad-tol" => self.gradient_tolerance = value()?.parse::<f32>()?,
+                "--param-tol" => self.parameter_tolerance = value()?.parse::<f32>()?,
+                "--time-limit" => {
+                    self.time_limit = Some(Duration::from_secs_f64(value()?.parse::<f64>()?))
+                }
+                "--schedule" => self.schedule = Some(parse_schedule(value()?)?),
+                "--clip-value" => self.grad_clip = Some(GradClip::Value(value()?.parse::<f32>()?)),
+                "--clip-norm" => self.grad_clip = Some(GradClip::Norm(value()?.parse::<f32>()?)),
+                "--check-finite" => self.check_finite = true,
+                "--square-free" => self.square_free = true,
+                "--starts" => self.starts = value()?.parse::<usize>()?,
+                "--sampling" => {
+                    self.sampling = match value()?.as_str() {
+                        "uniform" => Sampling::Uniform,
+                        "lhs" => Sampling::LatinHypercube,
+                        "sobol" => Sampling::Sobol,
+                        other => {
+                            return Err(FFError::Parse(format!("unknown sampling: {}", other)))
+                        }
+                    }
+                }
+                "--seed" => self.seed = value()?.parse::<u64>()?,
+                "--scaling" => {
+                    self.scaling = match value()?.as_str() {
+                        "none" => Scaling::Unscaled,
+                        "coefficients" => Scaling::CoefficientNorm,
+                        "initial" => Scaling::InitialResidual,
+                        other => return Err(FFError::Parse(format!("unknown scaling: {}", other))),
+                    }
+                }
+                "--loss" => self.loss = Loss::parse(value()?)?,
+                "--constraints" => {
+                    self.constraint_method = match value()?.as_str() {
+                        "penalty" => ConstraintMethod::Penalty,
+                        "barrier" => ConstraintMethod::Barrier,
+                        other => {
+                            return Err(FFError::Parse(format!(
+                                "unknown constraint method: {}",
+                                other
+                            )))
+                        }
+                    }
+                }
+                _ => self.path = Some(arg.clone()),
             }
         }
         return Ok(());
     }
+    // root-finding solvers that work on the residuals directly instead of an optimizer
+    pub fn least_squares_method(&self) -> Option<Method> {
+        return match self.solver.as_str() {
+            "newton" => Some(Method::Newton),
+            "gauss-newton" => Some(Method::GaussNewton),
+            "lm" => Some(Method::LevenbergMarquardt),
+            _ => None,
+        };
+    }
+    // reference solvers giving every complex root of univariate polynomials
+    pub fn all_roots(&self) -> bool {
+        return self.solver == "aberth" || self.solver == "companion";
+    }
+    pub fn optimizer(&self) -> Result<Optimizer> {
+        let lr = self.learning_rate;
+        return match self.solver.as_str() {
+            "sgd" => Ok(Optimizer::SGD(SGD::new(lr))),
+            "adam" => Ok(Optimizer::Adam(Adam::new(lr, 0.9, 0.99))),
+            "adagrad" => Ok(Optimizer::AdaGrad(AdaGrad::new(lr))),
+            "lbfgs" => Ok(Optimizer::LBFGS(LBFGS::new(self.history))),
+            other => Err(FFError::Parse(format!("unknown solver: {}", other))),
+        };
+    }
+    pub fn solver(&self) -> Solver {
+        let solver = Solver::new()
+            .with_max_iterations(self.iterations)
+            .with_loss_tolerance(self.loss_tolerance)
+            .with_gradient_tolerance(self.gradient_tolerance)
+            .with_parameter_tolerance(self.parameter_tolerance);
+        return match self.time_limit {
+            Some(limit) => solver.with_time_budget(limit),
+            None => solver,
+        };
+    }
+    pub fn scheduled_optimizer(&self) -> Result<Optimizer> {
+        let optimizer = self.optimizer()?;
+        return match &self.schedule {
+            Some(schedule) => Ok(Optimizer::Scheduled(Box::new(Scheduler::new(
+                optimizer,
+                schedule.clone(),
+            )))),
+            None => Ok(optimizer),
+        };
+    }
+}
+// step:N:GAMMA | exp:GAMMA | cosine:N:MIN_LR | plateau:FACTOR:PATIENCE[:MIN_LR]
+// | warmup:N[+SPEC]
+fn parse_schedule(spec: &str) -> Result<Schedule> {
+    if let Some((warmup, then)) = spec.split_once('+') {
+        return match parse_schedule(warmup)? {
+            Schedule::LinearWarmup { steps, .. } => Ok(Schedule::LinearWarmup {
+                steps,
+                then: Box::new(parse_schedule(then)?),
+            }),
+            _ => Err(FFError::Parse(format!(
+                "only warmup can be chained: {}",
+                spec
+            ))),
+        };
+    }
+    let words = spec.split(':').collect::<Vec<&str>>();
+    let arg = |i: usize| {
+        words.get(i).ok_or(FFError::Parse(format!(
+            "missing argument {} in schedule: {}",
+            i, spec
+        )))
+    };
+    return match words[0] {
+        "step" => Ok(Schedule::StepDecay {
+            step_size: arg(1)?.parse::<usize>()?,
+            gamma: arg(2)?.parse::<f32>()?,
+        }),
+        "exp" => Ok(Schedule::Exponential {
+            gamma: arg(1)?.parse::<f32>()?,
+        }),
+        "cosine" => Ok(Schedule::CosineAnnealing {
+            period: arg(1)?.parse::<usize>()?,
+            min_lr: arg(2)?.parse::<f32>()?,
+        }),
+        "plateau" => Ok(Schedule::ReduceOnPlateau {
+            factor: arg(1)?.parse::<f32>()?,
+            patience: arg(2)?.parse::<usize>()?,
+            threshold: 1e-4,
+            min_lr: match words.get(3) {
+                Some(min_lr) => min_lr.parse::<f32>()?,
+                None => 0.0,
+            },
+        }),
+        "warmup" => Ok(Schedule::LinearWarmup {
+            steps: arg(1)?.parse::<usize>()?,
+            then: Box::new(Schedule::Constant),
+        }),
+        other => Err(FFError::Parse(format!("unknown schedule: {}", other))),
+    };
 }
 //fn main() -> std::io::Result<()> {
 fn main() -> Result<()> {
-    // x+y = 3,x-y = 1
-    let context =
-        String::from("i@x@4.0@y@1.0\nc@1.0@x@1@c@1.0@y@1@c@-3.0\nc@1.0@x@1@c@-1.0@y@1@c@-1.0");
+    let mut solver_config = SolverConfig::new();
+    solver_config.parse_args(&std::env::args().skip(1).collect::<Vec<String>>())?;
+    let context = match &solver_config.path {
+        Some(path) => {
+            std::fs::read_to_string(path).map_err(|e| FFError::Parse(format!("{}: {}", path, e)))?
+        }
+        // x+y = 3,x-y = 1
+        None => {
+            String::from("i@x@4.0@y@1.0\nc@1.0@x@1@c@1.0@y@1@c@-3.0\nc@1.0@x@1@c@-1.0@y@1@c@-1.0")
+        }
+    };
     println!("\ncontext {}", context);
     let mut polynomial_onfig = PolynomialConfig::new();
     polynomial_onfig.parse(&context)?;
-    println!("\npolynomial_onfig {:?}", polynomial_onfig);
+    if solver_config.square_free {
+        // repeated roots leave the residual flat, deflate them before solving
+        for poly in polynomial_onfig.multi_poly.iter_mut() {
+            if poly.variables().len() == 1 {
+                *poly = poly.square_free_part()?;
+            }
+        }
+    }
+    println!("\nsystem");
+    for poly in polynomial_onfig.multi_poly.iter() {
+        println!("  {} = 0", poly);
+    }
+    println!("init {:?}", polynomial_onfig.init);
+    if !polynomial_onfig.bounds.is_empty() {
+        println!("bounds {:?}", polynomial_onfig.bounds);
+    }
+    if !polynomial_onfig.constraints.is_empty() {
+        println!("subject to");
+        for constraint in polynomial_onfig.constraints.iter() {
+            println!("  {}", constraint);
+        }
+        // only the optimizer path knows how to weigh constraints into its loss
+        if solver_config.optimizer().is_err() {
+            return Err(FFError::Parse(format!(
+                "the {} solver does not support constraints, use sgd, adam, adagrad or lbfgs",
+                solver_config.solver
+            )));
+        }
+    }
+
+    if solver_config.all_roots() {
+        for poly in polynomial_onfig.multi_poly.iter() {
+            let roots = match solver_config.solver.as_str() {
+                "aberth" => poly.complex_roots()?,
+                _ => poly.companion_roots()?,
+            };
+            println!("\nroots of {}", poly);
+            for root in roots.iter() {
+                println!("  {:?}", root);
+            }
+        }
+        return Ok(());
+    }
+    if solver_config.solver == "groebner" {
+        let basis = GroebnerBasis::new(&polynomial_onfig.multi_poly, MonomialOrder::Lex)?;
+        println!("\nlex groebner basis\n{}", basis);
+        println!("real solutions {:?}", basis.solve_real()?);
+        return Ok(());
+    }
+    if solver_config.solver == "resultant" {
+        // eliminate the first variable exactly, then back-substitute its roots
+        let [p, q] = polynomial_onfig.multi_poly.as_slice() else {
+            return Err(FFError::Polynomial(
+                "the resultant solver takes two equations".to_string(),
+            ));
+        };
+        let (p, q) = (p.to_exact()?, q.to_exact()?);
+        let name = (&p + &q).variables().first().cloned().unwrap_or_default();
+        println!("\nresultant in {}: {}", name, p.resultant(&q, &name));
+        println!("real solutions {:?}", solve_by_elimination(&p, &q, &name)?);
+        return Ok(());
+    }
+    if solver_config.solver == "multistart" {
+        // finite bounds limit the sampling box, otherwise it extends around the initial point
+        let region = polynomial_onfig
+            .init
+            .iter()
+            .map(|(name, value)| {
+                let (mut lower, mut upper) = (*value as f64 - 10.0, *value as f64 + 10.0);
+                if let Some(bound) = polynomial_onfig.bounds.get(name) {
+                    if bound.lower.is_finite() {
+                        lower = bound.lower as f64;
+                    }
+                    if bound.upper.is_finite() {
+                        upper = bound.upper as f64;
+                    }
+                }
+                (name.clone(), (lower, upper))
+            })
+            .collect::<HashMap<String, (f64, f64)>>();
+        let result = MultiStart::new()
+            .with_sampling(solver_config.sampling)
+            .with_starts(solver_config.starts)
+            .with_seed(solver_config.seed)
+            .with_solver(
+                LeastSquares::new(Method::LevenbergMarquardt)
+                    .with_max_iterations(solver_config.iterations)
+                    .with_bounds(polynomial_onfig.bounds.clone()),
+            )
+            .solve(&polynomial_onfig.multi_poly, &region)?;
+        println!(
+            "\n{} starts, {} converged, {} not converged, {} failed",
+            result.starts, result.converged, result.not_converged, result.failed
+        );
+        for solution in result.solutions.iter() {
+            println!("  {:?}", solution);
+        }
+        return Ok(());
+    }
+    if solver_config.solver == "homotopy" {
+        let result = Polynomial::solve_system(&polynomial_onfig.multi_poly)?;
+        println!(
+            "\n{} paths, {} diverged, {} failed",
+            result.paths, result.diverged, result.failed
+        );
+        for solution in result.solutions.iter() {
+            println!("  {:?}", solution);
+        }
+        println!("\nreal solutions {:?}", result.real_solutions());
+        return Ok(());
+    }
+    let scaling = EquationScaling::new(
+        &polynomial_onfig.multi_poly,
+        &polynomial_onfig.weights,
+        solver_config.scaling,
+        &polynomial_onfig.init,
+    )?;
+    println!("\nscaling {}", scaling);
+    if let Some(method) = solver_config.least_squares_method() {
+        if solver_config.loss != Loss::Squared {
+            return Err(FFError::Parse(format!(
+                "{} minimizes squared residuals, use an optimizer for the {:?} loss",
+                solver_config.solver, solver_config.loss
+            )));
+        }
+        // residuals in the result are the weighted and scaled ones
+        let result = LeastSquares::new(method)
+            .with_max_iterations(solver_config.iterations)
+            .with_bounds(polynomial_onfig.bounds.clone())
+            .solve(
+                &scaling.apply(&polynomial_onfig.multi_poly),
+                &polynomial_onfig.init,
+            )?;
+        println!("\nresult {:?}", result);
+        return Ok(());
+    }
 
+    let mut executor = |graph: Graph| -> Result<Executor> {
+        let mut exec = Executor::new(graph, solver_config.scheduled_optimizer()?);
+        exec.set_grad_clip(solver_config.grad_clip);
+        exec.set_check_finite(solver_config.check_finite);
+        for (name, bound) in polynomial_onfig.bounds.iter() {
+            exec.set_bound(name, *bound);
+        }
+        return Ok(exec);
+    };
+    if !polynomial_onfig.constraints.is_empty() {
+        let result = ConstrainedSolver::new(solver_config.constraint_method)
+            .with_loss(solver_config.loss)
+            .with_factors(scaling.factors())
+            .with_solver(solver_config.solver())
+            .solve(
+                &polynomial_onfig.multi_poly,
+                &polynomial_onfig.constraints,
+                &polynomial_onfig.init,
+                &mut executor,
+            )?;
+        println!(
+            "\n{:?} after {} outer iterations, weight {}, last solve {:?} after {} iterations, loss {}",
+            result.method,
+            result.outer_iterations,
+            result.weight,
+            result.report.termination,
+            result.report.iterations,
+            result.report.final_loss()
+        );
+        println!("para {:?}", result.report.parameters);
+        println!("residuals {:?}", result.report.residuals);
+        println!("violations {:?}", result.violations);
+        return Ok(());
+    }
     let mut allocator = NodeAllocator::new();
     let mut name_record = HashMap::<String, usize>::new();
-    let mut execs = polynomial_onfig
-        .multi_poly
-        .iter()
-        .map(|poly| {
-            let graph = poly.tranform_to_graph(&mut name_record, &mut allocator);
-            println!("\ngraph {:?}", graph);
-            //let mut exec = Executor::new(graph,Optimizer::Adam(Adam::new(0.01,0.9,0.99)));
-            let exec = Executor::new(graph, Optimizer::SGD(SGD::new(0.01)));
-            return exec;
-        })
-        .collect::<Vec<Executor>>();
+    let graph = tranform_weighted_system_to_graph(
+        &polynomial_onfig.multi_poly,
+        &scaling.factors(),
+        solver_config.loss,
+        &mut name_record,
+        &mut allocator,
+    )?;
+    println!("\ngraph {:?}", graph);
+    let mut exec = executor(graph)?;
     let mut s = Store::new();
-    for exec in execs.iter_mut() {
-        exec.init_parameter(&polynomial_onfig.init, &mut s)?;
-        exec.init_contants(&mut s);
-    }
-    for _ in 0..200 {
-        for exec in execs.iter_mut() {
-            exec.forward(&mut s)?;
-            exec.backward(&mut s)?;
-            exec.optimize(&mut s)?;
-            exec.clear_grad(&mut s)?;
-            let f = exec.get_output_value(&mut s);
-            println!("\nloss {:?}", f);
+    exec.init_parameter(&polynomial_onfig.init, &mut s)?;
+    exec.init_contants(&mut s);
+    let report = solver_config
+        .solver()
+        .solve(&mut exec, &mut s, &polynomial_onfig.multi_poly)?;
+    println!(
+        "\n{:?} after {} iterations, loss {}",
+        report.termination,
+        report.iterations,
+        report.final_loss()
+    );
+    println!("para {:?}", report.parameters);
+    println!("residuals {:?}", report.residuals);
+    // a single univariate equation: polish the optimizer's root and deflate for the others
+    if let [poly] = polynomial_onfig.multi_poly.as_slice() {
+        if let [name] = poly.variables().as_slice() {
+            println!(
+                "\nreal roots {:?}",
+                poly.real_roots_from(report.parameters[name] as f64)?
+            );
         }
-        let mut parameters = Vec::new();
-        execs.iter_mut().for_each(|exec| {
-            exec.get_parameters(&mut s)
-                .into_iter()
-                .for_each(|e| parameters.push(e));
-        });
-        println!("\npara {:?}", parameters);
     }
     Ok(())
 }
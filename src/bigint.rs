@@ -0,0 +1,416 @@
+use crate::error::{FFError, Result};
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+use std::ops::{Add, Mul, Neg, Sub};
+
+// arbitrary-precision signed integer, magnitude in base 2^32 with the least significant
+// digit first and no leading zero digits, zero is the empty magnitude and never negative
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+    negative: bool,
+    digits: Vec<u32>,
+}
+impl BigInt {
+    pub fn zero() -> Self {
+        return BigInt::default();
+    }
+    pub fn one() -> Self {
+        return BigInt::from(1);
+    }
+    pub fn is_zero(&self) -> bool {
+        return self.digits.is_empty();
+    }
+    pub fn is_negative(&self) -> bool {
+        return self.negative;
+    }
+    pub fn is_one(&self) -> bool {
+        return !self.negative && self.digits == [1];
+    }
+    pub fn abs(&self) -> BigInt {
+        return BigInt::from_parts(false, self.digits.clone());
+    }
+    // number of significant bits of the magnitude
+    pub fn bits(&self) -> usize {
+        return match self.digits.last() {
+            Some(top) => 32 * self.digits.len() - top.leading_zeros() as usize,
+            None => 0,
+        };
+    }
+    // quotient rounded toward zero and remainder with the sign of self, panics on zero
+    pub fn div_rem(&self, other: &BigInt) -> (BigInt, BigInt) {
+        assert!(!other.is_zero(), "big integer division by zero");
+        let (q, r) = div_rem_magnitude(&self.digits, &other.digits);
+        return (
+            BigInt::from_parts(self.negative != other.negative, q),
+            BigInt::from_parts(self.negative, r),
+        );
+    }
+    // non-negative greatest common divisor, gcd(0, 0) = 0
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        let (mut a, mut b) = (self.abs(), other.abs());
+        while !b.is_zero() {
+            let r = a.div_rem(&b).1;
+            a = b;
+            b = r;
+        }
+        return a;
+    }
+    pub fn pow(&self, exponent: u32) -> BigInt {
+        let mut res = BigInt::one();
+        let mut base = self.clone();
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                res = &res * &base;
+            }
+            base = &base * &base;
+            exponent >>= 1;
+        }
+        return res;
+    }
+    // multiplies by 2^bits
+    pub fn shl(&self, bits: usize) -> BigInt {
+        if self.is_zero() {
+            return self.clone();
+        }
+        let mut digits = vec![0; bits / 32];
+        let shift = bits % 32;
+        let mut carry = 0u32;
+        for d in self.digits.iter() {
+            let wide = (*d as u64) << shift;
+            digits.push(wide as u32 | carry);
+            carry = (wide >> 32) as u32;
+        }
+        digits.push(carry);
+        return BigInt::from_parts(self.negative, digits);
+    }
+    // nearest f64, infinite beyond its range
+    pub fn to_f64(&self) -> f64 {
+        let magnitude = self
+            .digits
+            .iter()
+            .rev()
+            .fold(0.0, |acc, d| acc * 4294967296.0 + *d as f64);
+        return if self.negative { -magnitude } else { magnitude };
+    }
+    // decimal integer with an optional sign
+    pub fn parse(text: &str) -> Result<BigInt> {
+        let text = text.trim();
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(FFError::Parse(format!("invalid integer '{}'", text)));
+        }
+        let mut magnitude = Vec::new();
+        for c in digits.chars() {
+            mul_add_small(&mut magnitude, 10, c.to_digit(10).unwrap());
+        }
+        return Ok(BigInt::from_parts(negative, magnitude));
+    }
+    fn from_parts(negative: bool, mut digits: Vec<u32>) -> BigInt {
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+        let negative = negative && !digits.is_empty();
+        return BigInt { negative, digits };
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> BigInt {
+        let mut magnitude = value.unsigned_abs();
+        let mut digits = Vec::new();
+        while magnitude > 0 {
+            digits.push(magnitude as u32);
+            magnitude >>= 32;
+        }
+        return BigInt::from_parts(value < 0, digits);
+    }
+}
+
+fn compare_magnitude(a: &[u32], b: &[u32]) -> Ordering {
+    return a
+        .len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()));
+}
+
+fn add_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        res.push(sum as u32);
+        carry = sum >> 32;
+    }
+    res.push(carry as u32);
+    return res;
+}
+
+// a - b for |a| >= |b|
+fn sub_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, d) in a.iter().enumerate() {
+        let diff = *d as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        res.push(diff as u32);
+        borrow = (diff < 0) as i64;
+    }
+    return res;
+}
+
+fn mul_magnitude(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut res = vec![0u32; a.len() + b.len()];
+    for (i, x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, y) in b.iter().enumerate() {
+            let wide = *x as u64 * *y as u64 + res[i + j] as u64 + carry;
+            res[i + j] = wide as u32;
+            carry = wide >> 32;
+        }
+        res[i + b.len()] = carry as u32;
+    }
+    return res;
+}
+
+// digits = digits * factor + addend
+fn mul_add_small(digits: &mut Vec<u32>, factor: u32, addend: u32) {
+    let mut carry = addend as u64;
+    for d in digits.iter_mut() {
+        let wide = *d as u64 * factor as u64 + carry;
+        *d = wide as u32;
+        carry = wide >> 32;
+    }
+    if carry > 0 {
+        digits.push(carry as u32);
+    }
+}
+
+// digits = digits / divisor, returns the remainder
+fn div_small(digits: &mut [u32], divisor: u32) -> u32 {
+    let mut remainder = 0u64;
+    for d in digits.iter_mut().rev() {
+        let wide = (remainder << 32) | *d as u64;
+        *d = (wide / divisor as u64) as u32;
+        remainder = wide % divisor as u64;
+    }
+    return remainder as u32;
+}
+
+// schoolbook long division, Knuth's algorithm D
+fn div_rem_magnitude(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if compare_magnitude(a, b) == Ordering::Less {
+        return (Vec::new(), a.to_vec());
+    }
+    if b.len() == 1 {
+        let mut q = a.to_vec();
+        let r = div_small(&mut q, b[0]);
+        return (q, vec![r]);
+    }
+    // normalize so that the top digit of the divisor has its high bit set
+    let shift = b[b.len() - 1].leading_zeros() as usize;
+    let v = BigInt::from_parts(false, b.to_vec()).shl(shift).digits;
+    let mut u = BigInt::from_parts(false, a.to_vec()).shl(shift).digits;
+    u.resize(a.len() + 1, 0);
+    let n = v.len();
+    let m = u.len() - n - 1;
+    let base = 1u64 << 32;
+    let mut q = vec![0u32; m + 1];
+    for j in (0..=m).rev() {
+        let top = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+        let mut qhat = top / v[n - 1] as u64;
+        let mut rhat = top % v[n - 1] as u64;
+        while qhat >= base || qhat * v[n - 2] as u64 > ((rhat << 32) | u[j + n - 2] as u64) {
+            qhat -= 1;
+            rhat += v[n - 1] as u64;
+            if rhat >= base {
+                break;
+            }
+        }
+        // u[j..=j+n] -= qhat * v
+        let (mut borrow, mut carry) = (0i64, 0u64);
+        for i in 0..n {
+            let product = qhat * v[i] as u64 + carry;
+            carry = product >> 32;
+            let diff = u[i + j] as i64 - borrow - (product & 0xffff_ffff) as i64;
+            u[i + j] = diff as u32;
+            borrow = (diff < 0) as i64;
+        }
+        let diff = u[j + n] as i64 - borrow - carry as i64;
+        u[j + n] = diff as u32;
+        // qhat was one too large, add v back
+        if diff < 0 {
+            qhat -= 1;
+            let mut carry = 0u64;
+            for i in 0..n {
+                let sum = u[i + j] as u64 + v[i] as u64 + carry;
+                u[i + j] = sum as u32;
+                carry = sum >> 32;
+            }
+            u[j + n] = u[j + n].wrapping_add(carry as u32);
+        }
+        q[j] = qhat as u32;
+    }
+    // unnormalize the remainder
+    let mut r = vec![0u32; n];
+    for i in 0..n {
+        let high = if shift > 0 && i + 1 < n {
+            u[i + 1] << (32 - shift)
+        } else {
+            0
+        };
+        r[i] = (u[i] >> shift) | high;
+    }
+    return (q, r);
+}
+
+impl Add<&BigInt> for &BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: &BigInt) -> BigInt {
+        if self.negative == rhs.negative {
+            return BigInt::from_parts(self.negative, add_magnitude(&self.digits, &rhs.digits));
+        }
+        return match compare_magnitude(&self.digits, &rhs.digits) {
+            Ordering::Less => {
+                BigInt::from_parts(rhs.negative, sub_magnitude(&rhs.digits, &self.digits))
+            }
+            _ => BigInt::from_parts(self.negative, sub_magnitude(&self.digits, &rhs.digits)),
+        };
+    }
+}
+impl Sub<&BigInt> for &BigInt {
+    type Output = BigInt;
+    fn sub(self, rhs: &BigInt) -> BigInt {
+        return self + &(-rhs);
+    }
+}
+impl Mul<&BigInt> for &BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: &BigInt) -> BigInt {
+        return BigInt::from_parts(
+            self.negative != rhs.negative,
+            mul_magnitude(&self.digits, &rhs.digits),
+        );
+    }
+}
+impl Neg for &BigInt {
+    type Output = BigInt;
+    fn neg(self) -> BigInt {
+        return BigInt::from_parts(!self.negative, self.digits.clone());
+    }
+}
+impl Add for BigInt {
+    type Output = BigInt;
+    fn add(self, rhs: BigInt) -> BigInt {
+        return &self + &rhs;
+    }
+}
+impl Sub for BigInt {
+    type Output = BigInt;
+    fn sub(self, rhs: BigInt) -> BigInt {
+        return &self - &rhs;
+    }
+}
+impl Mul for BigInt {
+    type Output = BigInt;
+    fn mul(self, rhs: BigInt) -> BigInt {
+        return &self * &rhs;
+    }
+}
+impl Neg for BigInt {
+    type Output = BigInt;
+    fn neg(self) -> BigInt {
+        return -&self;
+    }
+}
+impl PartialOrd for BigInt {
+    fn partial_cmp(&self, other: &BigInt) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+impl Ord for BigInt {
+    fn cmp(&self, other: &BigInt) -> Ordering {
+        return match (self.negative, other.negative) {
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+            (false, false) => compare_magnitude(&self.digits, &other.digits),
+            (true, true) => compare_magnitude(&other.digits, &self.digits),
+        };
+    }
+}
+impl Display for BigInt {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(fmt, "0");
+        }
+        // peel off nine decimal digits at a time
+        let mut digits = self.digits.clone();
+        let mut chunks = Vec::new();
+        while !digits.is_empty() {
+            chunks.push(div_small(&mut digits, 1_000_000_000));
+            while digits.last() == Some(&0) {
+                digits.pop();
+            }
+        }
+        let mut res = if self.negative {
+            "-".to_string()
+        } else {
+            String::new()
+        };
+        res.push_str(&chunks.pop().unwrap().to_string());
+        for chunk in chunks.iter().rev() {
+            res.push_str(&format!("{:09}", chunk));
+        }
+        return write!(fmt, "{}", res);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn big(text: &str) -> BigInt {
+        return BigInt::parse(text).unwrap();
+    }
+    #[test]
+    fn arithmetic() {
+        let a = big("123456789012345678901234567890");
+        let b = big("-987654321098765432109876543210");
+        assert_eq!((&a + &b).to_string(), "-864197532086419753208641975320");
+        assert_eq!((&a - &b).to_string(), "1111111110111111111011111111100");
+        assert_eq!(
+            (&a * &b).to_string(),
+            "-121932631137021795226185032733622923332237463801111263526900"
+        );
+        let (q, r) = b.div_rem(&a);
+        assert_eq!(
+            (q.to_string(), r.to_string()),
+            ("-8".to_string(), "-9000000000900000000090".to_string())
+        );
+        assert_eq!(&(&q * &a) + &r, b);
+        assert_eq!(
+            BigInt::from(2).pow(100).to_string(),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(big("-0"), BigInt::zero());
+        assert!(big("-5") < big("3") && big("-5") < big("-4"));
+        assert!(BigInt::parse("12a").is_err());
+    }
+    #[test]
+    fn long_division() {
+        // divisors over several digits exercise the qhat correction and add-back steps
+        let a = BigInt::from(2).pow(200) - BigInt::one();
+        for divisor in [
+            "18446744073709551617",
+            "340282366920938463463374607431768211455",
+            "79228162514264337593543950335",
+        ] {
+            let d = big(divisor);
+            let (q, r) = a.div_rem(&d);
+            assert!(r.abs() < d.abs() && !r.is_negative());
+            assert_eq!(&(&q * &d) + &r, a);
+        }
+        assert_eq!(big("84").gcd(&big("-36")), BigInt::from(12));
+    }
+}
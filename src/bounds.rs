@@ -0,0 +1,149 @@
+use crate::error::{FFError, Result};
+
+// keeps fractions and softplus outputs away from the bound itself, where the inverse maps blow up
+const INTERIOR: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoundMode {
+    // clamp the parameter after every optimizer step
+    Projection,
+    // parameter = lower + softplus(u) (or upper - softplus(u) with only an upper bound)
+    Softplus,
+    // parameter = lower + (upper - lower) * sigmoid(u), needs both bounds
+    Sigmoid,
+}
+
+// box constraint of a single parameter, use infinities for open sides
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bound {
+    pub lower: f32,
+    pub upper: f32,
+    pub mode: BoundMode,
+}
+impl Bound {
+    pub fn new(lower: f32, upper: f32) -> Self {
+        Bound {
+            lower,
+            upper,
+            mode: BoundMode::Projection,
+        }
+    }
+    pub fn with_mode(mut self, mode: BoundMode) -> Self {
+        self.mode = mode;
+        return self;
+    }
+    // rejects boxes that would make the maps return NaN or the wrong side
+    pub fn validate(&self) -> Result<()> {
+        if self.lower.is_nan() || self.upper.is_nan() {
+            return Err(FFError::Parse(format!("NaN in bound {:?}", self)));
+        }
+        if self.lower > self.upper {
+            return Err(FFError::Parse(format!(
+                "lower bound {} is above upper bound {}",
+                self.lower, self.upper
+            )));
+        }
+        if self.mode == BoundMode::Sigmoid && !(self.lower.is_finite() && self.upper.is_finite()) {
+            return Err(FFError::Parse(format!(
+                "sigmoid bounds need both sides finite, got [{}, {}]",
+                self.lower, self.upper
+            )));
+        }
+        return Ok(());
+    }
+    pub fn project(&self, value: f32) -> f32 {
+        return value.max(self.lower).min(self.upper);
+    }
+    // true when the optimizer works on an unconstrained u instead of the parameter itself
+    pub fn is_reparameterized(&self) -> bool {
+        return self.mode != BoundMode::Projection;
+    }
+    // parameter -> unconstrained u
+    pub fn to_internal(&self, value: f32) -> f32 {
+        let (lower, upper, value) = (self.lower as f64, self.upper as f64, value as f64);
+        let u = match self.mode {
+            BoundMode::Projection => value,
+            BoundMode::Softplus if lower.is_finite() => inverse_softplus(value - lower),
+            BoundMode::Softplus if upper.is_finite() => inverse_softplus(upper - value),
+            BoundMode::Softplus => value,
+            BoundMode::Sigmoid => {
+                let fraction = ((value - lower) / (upper - lower)).clamp(INTERIOR, 1.0 - INTERIOR);
+                (fraction / (1.0 - fraction)).ln()
+            }
+        };
+        return u as f32;
+    }
+    // unconstrained u -> parameter
+    pub fn to_external(&self, u: f32) -> f32 {
+        let (lower, upper, u) = (self.lower as f64, self.upper as f64, u as f64);
+        let value = match self.mode {
+            BoundMode::Projection => u,
+            BoundMode::Softplus if lower.is_finite() => lower + softplus(u),
+            BoundMode::Softplus if upper.is_finite() => upper - softplus(u),
+            BoundMode::Softplus => u,
+            BoundMode::Sigmoid => lower + (upper - lower) * sigmoid(u),
+        };
+        return value as f32;
+    }
+    // d parameter / d u, for the chain rule on gradients
+    pub fn derivative(&self, u: f32) -> f32 {
+        let (lower, upper, u) = (self.lower as f64, self.upper as f64, u as f64);
+        let d = match self.mode {
+            BoundMode::Projection => 1.0,
+            BoundMode::Softplus if lower.is_finite() => sigmoid(u),
+            BoundMode::Softplus if upper.is_finite() => -sigmoid(u),
+            BoundMode::Softplus => 1.0,
+            BoundMode::Sigmoid => (upper - lower) * sigmoid(u) * (1.0 - sigmoid(u)),
+        };
+        return d as f32;
+    }
+}
+
+fn softplus(u: f64) -> f64 {
+    return u.max(0.0) + (-u.abs()).exp().ln_1p();
+}
+fn inverse_softplus(y: f64) -> f64 {
+    let y = y.max(INTERIOR);
+    return y + (-(-y).exp_m1()).ln();
+}
+fn sigmoid(u: f64) -> f64 {
+    return 1.0 / (1.0 + (-u).exp());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn projection() {
+        let b = Bound::new(0.0, 2.0);
+        assert_eq!(b.project(-1.0), 0.0);
+        assert_eq!(b.project(1.5), 1.5);
+        assert_eq!(b.project(3.0), 2.0);
+        assert_eq!(Bound::new(f32::NEG_INFINITY, 1.0).project(-1e30), -1e30);
+    }
+    #[test]
+    fn reparameterization() {
+        let bounds = [
+            Bound::new(1.0, f32::INFINITY).with_mode(BoundMode::Softplus),
+            Bound::new(f32::NEG_INFINITY, 1.0).with_mode(BoundMode::Softplus),
+            Bound::new(-1.0, 3.0).with_mode(BoundMode::Sigmoid),
+        ];
+        for (b, value) in bounds.iter().zip([2.5, -4.0, 0.5]) {
+            let u = b.to_internal(value);
+            assert!((b.to_external(u) - value).abs() < 1e-5);
+            let h = 1e-3;
+            let numeric = (b.to_external(u + h) - b.to_external(u - h)) / (2.0 * h);
+            assert!((b.derivative(u) - numeric).abs() < 1e-2);
+            assert!(b.to_external(-50.0) >= b.lower && b.to_external(50.0) <= b.upper);
+        }
+    }
+    #[test]
+    fn validate() {
+        assert!(Bound::new(0.0, f32::INFINITY).validate().is_ok());
+        assert!(Bound::new(2.0, 1.0).validate().is_err());
+        assert!(Bound::new(f32::NAN, 1.0).validate().is_err());
+        let sigmoid = |lower, upper| Bound::new(lower, upper).with_mode(BoundMode::Sigmoid);
+        assert!(sigmoid(-1.0, 3.0).validate().is_ok());
+        assert!(sigmoid(0.0, f32::INFINITY).validate().is_err());
+    }
+}
@@ -0,0 +1,117 @@
+use crate::error::Result;
+use crate::rational::Rational;
+use std::fmt::{Debug, Display};
+use std::ops::{Add, Mul, Neg, Sub};
+
+// number type of the coefficients of `Term` and `Polynomial`: f32 for the graph
+// solvers, `Rational` for exact symbolic work
+pub trait Coefficient:
+    Clone
+    + Debug
+    + PartialEq
+    + Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Neg<Output = Self>
+{
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn from_usize(n: usize) -> Self;
+    // a numeric literal of the polynomial parser, e.g. `2.5` or `1e-3`
+    fn parse(text: &str) -> Result<Self>;
+    fn to_f32(&self) -> f32;
+    fn to_f64(&self) -> f64;
+    fn to_rational(&self) -> Result<Rational>;
+    fn is_negative(&self) -> bool;
+    fn is_zero(&self) -> bool {
+        return *self == Self::zero();
+    }
+    fn is_one(&self) -> bool {
+        return *self == Self::one();
+    }
+    fn abs(&self) -> Self {
+        if self.is_negative() {
+            return -self.clone();
+        }
+        return self.clone();
+    }
+    fn to_latex(&self) -> String {
+        return self.to_string();
+    }
+    fn pow(&self, exponent: usize) -> Self {
+        return (0..exponent).fold(Self::one(), |acc, _| acc * self.clone());
+    }
+}
+
+impl Coefficient for f32 {
+    fn zero() -> Self {
+        return 0.0;
+    }
+    fn one() -> Self {
+        return 1.0;
+    }
+    fn from_usize(n: usize) -> Self {
+        return n as f32;
+    }
+    fn parse(text: &str) -> Result<Self> {
+        return Ok(text.parse::<f32>()?);
+    }
+    fn to_f32(&self) -> f32 {
+        return *self;
+    }
+    fn to_f64(&self) -> f64 {
+        return *self as f64;
+    }
+    fn to_rational(&self) -> Result<Rational> {
+        return Rational::from_f32(*self);
+    }
+    fn is_negative(&self) -> bool {
+        return *self < 0.0;
+    }
+    fn pow(&self, exponent: usize) -> Self {
+        return self.powi(exponent as i32);
+    }
+}
+
+impl Coefficient for Rational {
+    fn zero() -> Self {
+        return Rational::zero();
+    }
+    fn one() -> Self {
+        return Rational::one();
+    }
+    fn from_usize(n: usize) -> Self {
+        return Rational::from_integer(n as i128);
+    }
+    fn parse(text: &str) -> Result<Self> {
+        return Rational::parse(text);
+    }
+    fn to_f32(&self) -> f32 {
+        return self.to_f64() as f32;
+    }
+    fn to_f64(&self) -> f64 {
+        return Rational::to_f64(self);
+    }
+    fn to_rational(&self) -> Result<Rational> {
+        return Ok(self.clone());
+    }
+    fn is_negative(&self) -> bool {
+        return Rational::is_negative(self);
+    }
+    fn is_zero(&self) -> bool {
+        return Rational::is_zero(self);
+    }
+    fn is_one(&self) -> bool {
+        return Rational::is_one(self);
+    }
+    fn abs(&self) -> Self {
+        return Rational::abs(self);
+    }
+    fn to_latex(&self) -> String {
+        if self.is_integer() {
+            return self.to_string();
+        }
+        return format!("\\frac{{{}}}{{{}}}", self.numerator(), self.denominator());
+    }
+}
@@ -0,0 +1,177 @@
+use std::fmt::{self, Display};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Complex { re, im }
+    }
+    pub fn from_real(re: f64) -> Self {
+        Complex { re, im: 0.0 }
+    }
+    // r * (cos(theta) + i sin(theta))
+    pub fn from_polar(r: f64, theta: f64) -> Self {
+        Complex {
+            re: r * theta.cos(),
+            im: r * theta.sin(),
+        }
+    }
+    pub fn zero() -> Self {
+        Complex::from_real(0.0)
+    }
+    pub fn one() -> Self {
+        Complex::from_real(1.0)
+    }
+    pub fn i() -> Self {
+        Complex::new(0.0, 1.0)
+    }
+    pub fn norm(&self) -> f64 {
+        return self.re.hypot(self.im);
+    }
+    pub fn norm_sqr(&self) -> f64 {
+        return self.re * self.re + self.im * self.im;
+    }
+    pub fn arg(&self) -> f64 {
+        return self.im.atan2(self.re);
+    }
+    pub fn conj(&self) -> Complex {
+        return Complex::new(self.re, -self.im);
+    }
+    pub fn inv(&self) -> Complex {
+        let d = self.norm_sqr();
+        return Complex::new(self.re / d, -self.im / d);
+    }
+    // principal square root
+    pub fn sqrt(&self) -> Complex {
+        return Complex::from_polar(self.norm().sqrt(), 0.5 * self.arg());
+    }
+    pub fn powi(&self, n: i32) -> Complex {
+        let mut res = Complex::one();
+        let mut base = if n < 0 { self.inv() } else { *self };
+        let mut n = n.unsigned_abs();
+        while n > 0 {
+            if n & 1 == 1 {
+                res *= base;
+            }
+            base *= base;
+            n >>= 1;
+        }
+        return res;
+    }
+    pub fn is_finite(&self) -> bool {
+        return self.re.is_finite() && self.im.is_finite();
+    }
+}
+
+impl Add for Complex {
+    type Output = Complex;
+    fn add(self, rhs: Complex) -> Complex {
+        return Complex::new(self.re + rhs.re, self.im + rhs.im);
+    }
+}
+impl Sub for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: Complex) -> Complex {
+        return Complex::new(self.re - rhs.re, self.im - rhs.im);
+    }
+}
+impl Mul for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: Complex) -> Complex {
+        return Complex::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        );
+    }
+}
+impl Div for Complex {
+    type Output = Complex;
+    // Smith's algorithm, avoids overflow in |rhs|^2
+    fn div(self, rhs: Complex) -> Complex {
+        if rhs.re.abs() >= rhs.im.abs() {
+            let r = rhs.im / rhs.re;
+            let d = rhs.re + rhs.im * r;
+            return Complex::new((self.re + self.im * r) / d, (self.im - self.re * r) / d);
+        }
+        let r = rhs.re / rhs.im;
+        let d = rhs.re * r + rhs.im;
+        return Complex::new((self.re * r + self.im) / d, (self.im * r - self.re) / d);
+    }
+}
+impl Neg for Complex {
+    type Output = Complex;
+    fn neg(self) -> Complex {
+        return Complex::new(-self.re, -self.im);
+    }
+}
+impl Add<f64> for Complex {
+    type Output = Complex;
+    fn add(self, rhs: f64) -> Complex {
+        return Complex::new(self.re + rhs, self.im);
+    }
+}
+impl Sub<f64> for Complex {
+    type Output = Complex;
+    fn sub(self, rhs: f64) -> Complex {
+        return Complex::new(self.re - rhs, self.im);
+    }
+}
+impl Mul<f64> for Complex {
+    type Output = Complex;
+    fn mul(self, rhs: f64) -> Complex {
+        return Complex::new(self.re * rhs, self.im * rhs);
+    }
+}
+impl Div<f64> for Complex {
+    type Output = Complex;
+    fn div(self, rhs: f64) -> Complex {
+        return Complex::new(self.re / rhs, self.im / rhs);
+    }
+}
+impl AddAssign for Complex {
+    fn add_assign(&mut self, rhs: Complex) {
+        *self = *self + rhs;
+    }
+}
+impl SubAssign for Complex {
+    fn sub_assign(&mut self, rhs: Complex) {
+        *self = *self - rhs;
+    }
+}
+impl MulAssign for Complex {
+    fn mul_assign(&mut self, rhs: Complex) {
+        *self = *self * rhs;
+    }
+}
+impl Display for Complex {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.im < 0.0 {
+            return write!(fmt, "{} - {}i", self.re, -self.im);
+        }
+        return write!(fmt, "{} + {}i", self.re, self.im);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn arithmetic() {
+        let a = Complex::new(1.0, 2.0);
+        let b = Complex::new(3.0, -1.0);
+        assert_eq!(a + b, Complex::new(4.0, 1.0));
+        assert_eq!(a - b, Complex::new(-2.0, 3.0));
+        assert_eq!(a * b, Complex::new(5.0, 5.0));
+        let q = a / b;
+        assert!(((q * b) - a).norm() < 1e-12);
+        assert!((Complex::new(-4.0, 0.0).sqrt() - Complex::new(0.0, 2.0)).norm() < 1e-12);
+        assert!((Complex::i().powi(3) - Complex::new(0.0, -1.0)).norm() < 1e-12);
+        assert!((a.powi(-2) * a * a - Complex::one()).norm() < 1e-12);
+        assert_eq!(a.to_string(), "1 + 2i");
+        assert_eq!(b.to_string(), "3 - 1i");
+    }
+}
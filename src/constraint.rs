@@ -0,0 +1,347 @@
+use crate::error::{FFError, Result};
+use crate::executor::Executor;
+use crate::graph::{self, Graph, NodeAllocator};
+use crate::loss::Loss;
+use crate::ops::ScalarFunction;
+use crate::polynomial::{tranform_polynomial_to_graph, weighted_losses, Polynomial};
+use crate::solver::{SolveReport, Solver};
+use crate::store::Store;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+// an inequality g(x) <= 0
+#[derive(Debug, Clone, PartialEq)]
+pub struct Constraint {
+    poly: Polynomial,
+}
+impl Constraint {
+    pub fn new(poly: Polynomial) -> Self {
+        Constraint { poly }
+    }
+    // `lhs <= rhs` or `lhs >= rhs`, both sides in the notation of `Polynomial::parse`
+    pub fn parse(text: &str) -> Result<Self> {
+        if let Some((lhs, rhs)) = text.split_once("<=") {
+            return Ok(Constraint::new(
+                &Polynomial::parse(lhs)? - &Polynomial::parse(rhs)?,
+            ));
+        }
+        if let Some((lhs, rhs)) = text.split_once(">=") {
+            return Ok(Constraint::new(
+                &Polynomial::parse(rhs)? - &Polynomial::parse(lhs)?,
+            ));
+        }
+        return Err(FFError::Parse(format!(
+            "expected '<=' or '>=' in constraint: {}",
+            text
+        )));
+    }
+    pub fn get_polynomial(&self) -> &Polynomial {
+        return &self.poly;
+    }
+    // max(0, g(x)), zero when the constraint holds
+    pub fn violation(&self, values: &HashMap<String, f64>) -> Result<f64> {
+        return Ok(self.poly.evaluate(values)?.max(0.0));
+    }
+}
+impl Display for Constraint {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{} <= 0", self.poly)
+    }
+}
+
+// how constraints enter the loss, as weight * sum of phi(g_j)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConstraintMethod {
+    // phi(g) = max(0, g)^2, the weight grows until the violations are small
+    Penalty,
+    // phi(g) = -log(-g), infinite outside the feasible region, the weight shrinks so the
+    // iterates approach the boundary from inside. needs a strictly feasible start
+    Barrier,
+}
+impl ScalarFunction for ConstraintMethod {
+    fn value(&self, g: f32) -> f32 {
+        return match self {
+            ConstraintMethod::Penalty => g.max(0.0).powi(2),
+            ConstraintMethod::Barrier if g < 0.0 => -(-g).ln(),
+            ConstraintMethod::Barrier => f32::INFINITY,
+        };
+    }
+    // d value / d g
+    fn derivative(&self, g: f32) -> f32 {
+        return match self {
+            ConstraintMethod::Penalty => 2.0 * g.max(0.0),
+            ConstraintMethod::Barrier if g < 0.0 => -1.0 / g,
+            ConstraintMethod::Barrier => f32::INFINITY,
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConstrainedReport {
+    pub method: ConstraintMethod,
+    // unconstrained solves, one per weight
+    pub outer_iterations: usize,
+    // constraint weight of the last solve
+    pub weight: f32,
+    // the last solve, its parameters are the result
+    pub report: SolveReport,
+    // max(0, g_j) at the result for every constraint, evaluated in f64
+    pub violations: Vec<f64>,
+}
+impl ConstrainedReport {
+    pub fn max_violation(&self) -> f64 {
+        return self.violations.iter().fold(0.0, |m: f64, v| m.max(*v));
+    }
+}
+
+// solves equations subject to inequality constraints as a sequence of unconstrained
+// problems, each started from the previous solution with a new constraint weight
+pub struct ConstrainedSolver {
+    method: ConstraintMethod,
+    weight: f32,
+    factor: f32,
+    outer_iterations: usize,
+    tolerance: f64,
+    loss: Loss,
+    factors: Vec<f32>,
+    solver: Solver,
+}
+impl ConstrainedSolver {
+    pub fn new(method: ConstraintMethod) -> Self {
+        ConstrainedSolver {
+            method,
+            weight: 1.0,
+            factor: match method {
+                ConstraintMethod::Penalty => 10.0,
+                ConstraintMethod::Barrier => 0.1,
+            },
+            outer_iterations: 10,
+            tolerance: 1e-4,
+            loss: Loss::Squared,
+            factors: Vec::new(),
+            solver: Solver::new(),
+        }
+    }
+    // initial weight and what it is multiplied by after each solve
+    pub fn with_weight(mut self, weight: f32, factor: f32) -> Self {
+        self.weight = weight;
+        self.factor = factor;
+        return self;
+    }
+    pub fn with_outer_iterations(mut self, outer_iterations: usize) -> Self {
+        self.outer_iterations = outer_iterations;
+        return self;
+    }
+    // penalty stops once every violation is at most this, barrier once weight times the
+    // number of constraints is, which bounds how far it can be from the boundary optimum
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        return self;
+    }
+    pub fn with_loss(mut self, loss: Loss) -> Self {
+        self.loss = loss;
+        return self;
+    }
+    // one factor per equation as from `EquationScaling::factors`, empty for all 1
+    pub fn with_factors(mut self, factors: Vec<f32>) -> Self {
+        self.factors = factors;
+        return self;
+    }
+    // the inner solver, the barrier term can make the loss negative so its loss tolerance
+    // is ignored for barrier solves
+    pub fn with_solver(mut self, solver: Solver) -> Self {
+        self.solver = solver;
+        return self;
+    }
+    // `executor` wraps each graph with the optimizer and settings to use, a fresh one per
+    // weight since the loss it optimizes changes
+    pub fn solve(
+        &self,
+        polys: &[Polynomial],
+        constraints: &[Constraint],
+        init: &HashMap<String, f32>,
+        executor: &mut dyn FnMut(Graph) -> Result<Executor>,
+    ) -> Result<ConstrainedReport> {
+        if polys.is_empty() && constraints.is_empty() {
+            return Err(FFError::Polynomial(
+                "nothing to solve, no equations or constraints".to_string(),
+            ));
+        }
+        let factors = match self.factors.len() {
+            0 => vec![1.0; polys.len()],
+            n if n == polys.len() => self.factors.clone(),
+            n => {
+                return Err(FFError::Polynomial(format!(
+                    "{} factors for {} equations",
+                    n,
+                    polys.len()
+                )))
+            }
+        };
+        let solver = match self.method {
+            ConstraintMethod::Penalty => self.solver.clone(),
+            ConstraintMethod::Barrier => self.solver.clone().with_loss_tolerance(f32::NEG_INFINITY),
+        };
+        let mut parameters = init.clone();
+        if self.method == ConstraintMethod::Barrier {
+            let violated = violations(constraints, &parameters, true)?;
+            if let Some(j) = violated.iter().position(|v| *v >= 0.0) {
+                return Err(FFError::Polynomial(format!(
+                    "the barrier method needs a strictly feasible start, {} does not hold",
+                    constraints[j]
+                )));
+            }
+        }
+        let mut weight = self.weight;
+        let mut outer_iterations = 0;
+        loop {
+            let graph = self.graph(polys, &factors, constraints, weight)?;
+            let mut exec = executor(graph)?;
+            let mut s = Store::new();
+            exec.init_parameter(&parameters, &mut s)?;
+            exec.init_contants(&mut s);
+            let report = solver.solve(&mut exec, &mut s, polys)?;
+            outer_iterations += 1;
+            parameters.extend(report.parameters.clone());
+            let violations = violations(constraints, &parameters, false)?;
+            let done = match self.method {
+                ConstraintMethod::Penalty => violations.iter().all(|v| *v <= self.tolerance),
+                ConstraintMethod::Barrier => {
+                    (weight as f64) * constraints.len() as f64 <= self.tolerance
+                }
+            };
+            if done || outer_iterations >= self.outer_iterations || !report.final_loss().is_finite()
+            {
+                return Ok(ConstrainedReport {
+                    method: self.method,
+                    outer_iterations,
+                    weight,
+                    report,
+                    violations,
+                });
+            }
+            weight *= self.factor;
+        }
+    }
+    // sum of equation losses + weight * sum of phi(g_j)
+    fn graph(
+        &self,
+        polys: &[Polynomial],
+        factors: &[f32],
+        constraints: &[Constraint],
+        weight: f32,
+    ) -> Result<Graph> {
+        let mut g = Graph::new();
+        let mut allocator = NodeAllocator::new();
+        let mut name_to_index = HashMap::new();
+        let mut terms = weighted_losses(
+            polys,
+            factors,
+            self.loss,
+            &mut g,
+            &mut name_to_index,
+            &mut allocator,
+        )?;
+        if !constraints.is_empty() {
+            let phis = constraints
+                .iter()
+                .map(|c| {
+                    let output = tranform_polynomial_to_graph(
+                        &c.poly,
+                        &mut g,
+                        &mut name_to_index,
+                        &mut allocator,
+                    );
+                    return g.constraint(output, self.method, &mut allocator);
+                })
+                .collect::<Vec<usize>>();
+            let phi = graph::sum(&phis, &mut g, &mut allocator);
+            let weight = g.constant(weight, &mut allocator);
+            terms.push(g.mul(phi, weight, &mut allocator));
+        }
+        let loss = graph::sum(&terms, &mut g, &mut allocator);
+        g.output(loss);
+        return Ok(g);
+    }
+}
+
+// max(0, g_j), or g_j itself when `signed`
+fn violations(
+    constraints: &[Constraint],
+    parameters: &HashMap<String, f32>,
+    signed: bool,
+) -> Result<Vec<f64>> {
+    let values = parameters
+        .iter()
+        .map(|(name, value)| (name.clone(), *value as f64))
+        .collect::<HashMap<String, f64>>();
+    return constraints
+        .iter()
+        .map(|c| match signed {
+            true => c.poly.evaluate(&values),
+            false => c.violation(&values),
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lbfgs::LBFGS;
+    use crate::optimizer::Optimizer;
+    fn lbfgs(graph: Graph) -> Result<Executor> {
+        return Ok(Executor::new(graph, Optimizer::LBFGS(LBFGS::new(8))));
+    }
+    #[test]
+    fn parse() {
+        let c = Constraint::parse("x^2 + y^2 <= 1").unwrap();
+        assert_eq!(
+            *c.get_polynomial(),
+            Polynomial::parse("x^2 + y^2 - 1").unwrap()
+        );
+        let c = Constraint::parse("x >= 2").unwrap();
+        let values = HashMap::from([("x".to_string(), 0.5)]);
+        assert_eq!(c.violation(&values).unwrap(), 1.5);
+        assert!(Constraint::parse("x = 1").is_err());
+    }
+    #[test]
+    fn penalty_and_barrier() {
+        // x = 2 is wanted but x <= 1 is allowed, the constrained optimum is the boundary
+        let polys = vec![Polynomial::parse("x - 2").unwrap()];
+        let constraints = vec![Constraint::parse("x <= 1").unwrap()];
+        let init = HashMap::from([("x".to_string(), 0.0)]);
+        let solver = Solver::new()
+            .with_max_iterations(100)
+            .with_parameter_tolerance(1e-7);
+        for method in [ConstraintMethod::Penalty, ConstraintMethod::Barrier] {
+            let result = ConstrainedSolver::new(method)
+                .with_solver(solver.clone())
+                .solve(&polys, &constraints, &init, &mut lbfgs)
+                .unwrap();
+            assert!(
+                (result.report.parameters["x"] - 1.0).abs() < 1e-3,
+                "{:?}",
+                result
+            );
+            assert!(result.max_violation() <= 1e-4);
+            assert!(result.outer_iterations > 1);
+        }
+        // of the two roots of x^2 - 4 only x = 2 satisfies x >= 0
+        let polys = vec![Polynomial::parse("x^2 - 4").unwrap()];
+        let constraints = vec![Constraint::parse("x >= 0").unwrap()];
+        let init = HashMap::from([("x".to_string(), -3.0)]);
+        let result = ConstrainedSolver::new(ConstraintMethod::Penalty)
+            .with_solver(solver.clone())
+            .solve(&polys, &constraints, &init, &mut lbfgs)
+            .unwrap();
+        assert!((result.report.parameters["x"] - 2.0).abs() < 1e-3);
+        assert_eq!(result.violations, vec![0.0]);
+        assert!(ConstrainedSolver::new(ConstraintMethod::Barrier)
+            .solve(&polys, &constraints, &init, &mut lbfgs)
+            .is_err());
+        assert!(ConstrainedSolver::new(ConstraintMethod::Penalty)
+            .with_factors(vec![1.0, 1.0])
+            .solve(&polys, &constraints, &init, &mut lbfgs)
+            .is_err());
+    }
+}
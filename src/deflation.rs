@@ -0,0 +1,210 @@
+use crate::division::trim;
+use crate::error::{FFError, Result};
+use crate::polynomial::{horner, Polynomial};
+use crate::sturm::SturmSequence;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RealRoot {
+    pub value: f64,
+    // times the root was deflated out of the polynomial
+    pub multiplicity: usize,
+    // |p(value)| for the original, undeflated polynomial
+    pub residual: f64,
+}
+
+// finds a real root, polishes it with Newton's method on the original polynomial,
+// divides it out and repeats until no real roots are left
+pub struct Deflation {
+    max_iterations: usize,
+    tolerance: f64,
+    merge_tolerance: f64,
+}
+impl Deflation {
+    pub fn new() -> Self {
+        Deflation {
+            max_iterations: 100,
+            tolerance: 1e-15,
+            merge_tolerance: 1e-6,
+        }
+    }
+    // Newton stops after `max_iterations` or a step below `tolerance` relative to the root
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        return self;
+    }
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        return self;
+    }
+    // roots closer than this (relative to their size) count as one multiple root, Newton
+    // only converges to about sqrt(eps) on a double root
+    pub fn with_merge_tolerance(mut self, merge_tolerance: f64) -> Self {
+        self.merge_tolerance = merge_tolerance;
+        return self;
+    }
+    // Newton's method in f64 from x0 on coefficients lowest degree first
+    pub fn polish(&self, coefficients: &[f64], x0: f64) -> f64 {
+        let mut x = x0;
+        for _ in 0..self.max_iterations {
+            let (p, dp) = value_and_derivative(coefficients, x);
+            if p == 0.0 || dp == 0.0 {
+                break;
+            }
+            let next = x - p / dp;
+            if !next.is_finite() {
+                break;
+            }
+            let step = (next - x).abs();
+            x = next;
+            if step <= self.tolerance * x.abs().max(1.0) {
+                break;
+            }
+        }
+        return x;
+    }
+    // all real roots in increasing order, `start` is a root found elsewhere (by an
+    // optimizer, say) that is polished and deflated first, when it is one
+    pub fn enumerate(&self, coefficients: &[f64], start: Option<f64>) -> Result<Vec<RealRoot>> {
+        let original = trim(coefficients);
+        if original.iter().all(|c| *c == 0.0) {
+            return Err(FFError::Polynomial(
+                "the zero polynomial has no isolated roots".to_string(),
+            ));
+        }
+        let mut roots = Vec::<RealRoot>::new();
+        let mut current = original.clone();
+        let mut start = start
+            .map(|x| self.polish(&original, x))
+            .filter(|x| is_root(&original, *x));
+        while current.len() > 1 {
+            let guess = match start.take() {
+                Some(x) => x,
+                None => match self.next_root(&current) {
+                    Some(x) => x,
+                    None => break,
+                },
+            };
+            let root = self.polish(&original, guess);
+            current = synthetic_division(&current, root).0;
+            match roots
+                .iter_mut()
+                .find(|r| (r.value - root).abs() <= self.merge_tolerance * root.abs().max(1.0))
+            {
+                Some(r) => r.multiplicity += 1,
+                None => roots.push(RealRoot {
+                    value: root,
+                    multiplicity: 1,
+                    residual: horner(&original, root).abs(),
+                }),
+            }
+        }
+        roots.sort_by(|a, b| a.value.total_cmp(&b.value));
+        return Ok(roots);
+    }
+    // a real root of the deflated polynomial, started from the middle of its first
+    // Sturm isolating interval
+    fn next_root(&self, current: &[f64]) -> Option<f64> {
+        let sturm = SturmSequence::new(current).ok()?;
+        let bound = sturm.root_bound();
+        let (lo, hi) = *sturm.isolate(-bound, bound).first()?;
+        return Some(self.polish(current, 0.5 * (lo + hi)).clamp(lo, hi));
+    }
+}
+impl Default for Deflation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// (q, r) with p(x) = (x - root) q(x) + r
+pub fn synthetic_division(coefficients: &[f64], root: f64) -> (Vec<f64>, f64) {
+    let n = coefficients.len() - 1;
+    if n == 0 {
+        return (vec![0.0], coefficients[0]);
+    }
+    let mut quotient = vec![0.0; n];
+    quotient[n - 1] = coefficients[n];
+    for k in (1..n).rev() {
+        quotient[k - 1] = coefficients[k] + root * quotient[k];
+    }
+    let remainder = coefficients[0] + root * quotient[0];
+    return (quotient, remainder);
+}
+
+fn value_and_derivative(c: &[f64], x: f64) -> (f64, f64) {
+    let (mut p, mut dp) = (0.0, 0.0);
+    for coefficient in c.iter().rev() {
+        dp = dp * x + p;
+        p = p * x + coefficient;
+    }
+    return (p, dp);
+}
+
+// |p(x)| within a loose multiple of the rounding error of evaluating p at x
+fn is_root(c: &[f64], x: f64) -> bool {
+    let magnitude = c.iter().rev().fold(0.0, |acc, a| acc * x.abs() + a.abs());
+    return horner(c, x).abs() <= 1e-8 * magnitude;
+}
+
+impl Polynomial {
+    // divides a univariate polynomial by (x - root), returning quotient and remainder
+    pub fn deflate(&self, root: f64) -> Result<(Polynomial, f64)> {
+        let name = self.variables().first().cloned().unwrap_or_default();
+        let (q, r) = synthetic_division(&self.univariate()?, root);
+        return Ok((Polynomial::from_univariate(&name, &q), r));
+    }
+    // Newton-polished root near x0 with its residual
+    pub fn polish_root(&self, x0: f64) -> Result<RealRoot> {
+        let c = self.univariate()?;
+        let value = Deflation::new().polish(&c, x0);
+        return Ok(RealRoot {
+            value,
+            multiplicity: 1,
+            residual: horner(&c, value).abs(),
+        });
+    }
+    // every real root, by repeated polishing and deflation
+    pub fn real_roots(&self) -> Result<Vec<RealRoot>> {
+        return Deflation::new().enumerate(&self.univariate()?, None);
+    }
+    // every real root, starting the deflation from a root found by an optimizer
+    pub fn real_roots_from(&self, start: f64) -> Result<Vec<RealRoot>> {
+        return Deflation::new().enumerate(&self.univariate()?, Some(start));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::product;
+    fn assert_roots(roots: &[RealRoot], expected: &[(f64, usize)]) {
+        assert_eq!(roots.len(), expected.len(), "{:?}", roots);
+        for (root, (value, multiplicity)) in roots.iter().zip(expected.iter()) {
+            assert!((root.value - value).abs() < 1e-6, "{:?} != {}", root, value);
+            assert_eq!(root.multiplicity, *multiplicity);
+            assert!(root.residual < 1e-9);
+        }
+    }
+    #[test]
+    fn synthetic() {
+        let p = Polynomial::parse("x^3 - 2x^2 - 4").unwrap();
+        let (q, r) = p.deflate(3.0).unwrap();
+        assert_eq!(q, Polynomial::parse("x^2 + x + 3").unwrap());
+        assert_eq!(r, 5.0);
+    }
+    #[test]
+    fn enumerate() {
+        let p = product(&["x - 1", "x + 2", "x - 3", "x^2 + 1"]);
+        assert_roots(&p.real_roots().unwrap(), &[(-2.0, 1), (1.0, 1), (3.0, 1)]);
+        // a rough optimizer result is polished before deflation
+        let roots = p.real_roots_from(2.98).unwrap();
+        assert_roots(&roots, &[(-2.0, 1), (1.0, 1), (3.0, 1)]);
+        let p = product(&["x - 1", "x - 1", "x + 0.5"]);
+        assert_roots(&p.real_roots().unwrap(), &[(-0.5, 1), (1.0, 2)]);
+        assert!(Polynomial::parse("x^2 + 1")
+            .unwrap()
+            .real_roots()
+            .unwrap()
+            .is_empty());
+    }
+}
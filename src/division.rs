@@ -0,0 +1,278 @@
+use crate::error::{FFError, Result};
+use crate::polynomial::Polynomial;
+
+// remainder coefficients below this (relative to the dividend) count as zero in the
+// tolerant GCD behind square-free factorization, coefficients are only f32 accurate
+pub const DEFAULT_GCD_TOLERANCE: f64 = 1e-5;
+
+// coefficient vectors are lowest degree first
+
+// quotient and remainder of p / q
+pub fn div_rem(p: &[f64], q: &[f64]) -> Result<(Vec<f64>, Vec<f64>)> {
+    let q = trim(q);
+    if is_zero(&q) {
+        return Err(FFError::Polynomial(
+            "division by the zero polynomial".to_string(),
+        ));
+    }
+    let m = q.len() - 1;
+    let mut r = trim(p);
+    if r.len() <= m {
+        return Ok((vec![0.0], r));
+    }
+    let mut quotient = vec![0.0; r.len() - m];
+    for shift in (0..quotient.len()).rev() {
+        let factor = r[shift + m] / q[m];
+        quotient[shift] = factor;
+        for (i, c) in q.iter().enumerate() {
+            r[shift + i] -= factor * c;
+        }
+    }
+    // the remainder has lower degree than q, a constant q divides exactly
+    r.truncate(m.max(1));
+    if m == 0 {
+        r[0] = 0.0;
+    }
+    return Ok((trim(&quotient), trim(&r)));
+}
+
+// monic greatest common divisor by the Euclidean algorithm, remainders are scaled to a
+// largest coefficient of one and coefficients within `tolerance` of zero are dropped
+pub fn gcd(p: &[f64], q: &[f64], tolerance: f64) -> Vec<f64> {
+    let (mut a, mut b) = (normalize(&trim(p)), normalize(&trim(q)));
+    if a.len() < b.len() {
+        std::mem::swap(&mut a, &mut b);
+    }
+    while !is_zero(&b) {
+        let (_, r) = div_rem(&a, &b).unwrap();
+        a = b;
+        b = normalize(&cut(&r, tolerance));
+    }
+    return monic(&a);
+}
+
+// Yun's algorithm: (f, i) pairs with p = lc(p) * prod f^i, every f monic with simple
+// roots and pairwise coprime, constant factors are left out
+pub fn square_free(p: &[f64], tolerance: f64) -> Result<Vec<(Vec<f64>, usize)>> {
+    let p = trim(p);
+    if is_zero(&p) {
+        return Err(FFError::Polynomial(
+            "the zero polynomial has no square-free factorization".to_string(),
+        ));
+    }
+    let mut factors = Vec::new();
+    if p.len() == 1 {
+        return Ok(factors);
+    }
+    let dp = derivative(&p);
+    let a = gcd(&p, &dp, tolerance);
+    let mut b = div_rem(&p, &a)?.0;
+    let mut d = yun_step(&div_rem(&dp, &a)?.0, &b, tolerance);
+    let mut i = 1;
+    // each pass peels off the factors of multiplicity i, so p's degree bounds the passes
+    while b.len() > 1 && i < p.len() {
+        let a = gcd(&b, &d, tolerance);
+        b = div_rem(&b, &a)?.0;
+        d = yun_step(&div_rem(&d, &a)?.0, &b, tolerance);
+        if a.len() > 1 {
+            factors.push((a, i));
+        }
+        i += 1;
+    }
+    return Ok(factors);
+}
+
+// c - b', cut to zero where it cancels down to rounding noise
+fn yun_step(c: &[f64], b: &[f64], tolerance: f64) -> Vec<f64> {
+    let db = derivative(b);
+    let scale = max_abs(c).max(max_abs(&db));
+    let d = (0..c.len().max(db.len()))
+        .map(|i| c.get(i).unwrap_or(&0.0) - db.get(i).unwrap_or(&0.0))
+        .collect::<Vec<f64>>();
+    return trim(&cut(&d, tolerance * scale));
+}
+
+// drops zero high-order coefficients, keeping at least one
+pub(crate) fn trim(p: &[f64]) -> Vec<f64> {
+    let len = p.iter().rposition(|c| *c != 0.0).map_or(1, |i| i + 1);
+    return p[..len.min(p.len())].to_vec();
+}
+
+pub(crate) fn derivative(p: &[f64]) -> Vec<f64> {
+    let d = p
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(i, c)| i as f64 * c)
+        .collect::<Vec<f64>>();
+    return if d.is_empty() { vec![0.0] } else { d };
+}
+
+// sets coefficients within `threshold` of zero to zero
+pub(crate) fn cut(p: &[f64], threshold: f64) -> Vec<f64> {
+    let cut = p
+        .iter()
+        .map(|c| if c.abs() <= threshold { 0.0 } else { *c })
+        .collect::<Vec<f64>>();
+    return trim(&cut);
+}
+
+fn is_zero(p: &[f64]) -> bool {
+    return p.iter().all(|c| *c == 0.0);
+}
+
+fn max_abs(p: &[f64]) -> f64 {
+    return p.iter().fold(0.0, |m, c| m.max(c.abs()));
+}
+
+fn normalize(p: &[f64]) -> Vec<f64> {
+    let scale = max_abs(p);
+    if scale == 0.0 {
+        return p.to_vec();
+    }
+    return p.iter().map(|c| c / scale).collect();
+}
+
+fn monic(p: &[f64]) -> Vec<f64> {
+    let lead = p[p.len() - 1];
+    if lead == 0.0 {
+        return p.to_vec();
+    }
+    return p.iter().map(|c| c / lead).collect();
+}
+
+impl Polynomial {
+    // quotient and remainder of univariate polynomials in the same variable
+    pub fn div_rem(&self, divisor: &Polynomial) -> Result<(Polynomial, Polynomial)> {
+        let name = common_variable(&[self, divisor])?;
+        let (q, r) = div_rem(
+            &self.univariate_coefficients(&name)?,
+            &divisor.univariate_coefficients(&name)?,
+        )?;
+        return Ok((
+            Polynomial::from_univariate(&name, &q),
+            Polynomial::from_univariate(&name, &r),
+        ));
+    }
+    // monic greatest common divisor, exact up to f64 rounding
+    pub fn gcd(&self, other: &Polynomial) -> Result<Polynomial> {
+        return self.gcd_with_tolerance(other, 0.0);
+    }
+    // monic greatest common divisor treating remainders whose coefficients are within
+    // `tolerance` of zero (relative to the dividend) as exact
+    pub fn gcd_with_tolerance(&self, other: &Polynomial, tolerance: f64) -> Result<Polynomial> {
+        let name = common_variable(&[self, other])?;
+        let g = gcd(
+            &self.univariate_coefficients(&name)?,
+            &other.univariate_coefficients(&name)?,
+            tolerance,
+        );
+        return Ok(Polynomial::from_univariate(&name, &g));
+    }
+    // (factor, multiplicity) pairs, see `square_free`, with `DEFAULT_GCD_TOLERANCE`
+    pub fn square_free_factorization(&self) -> Result<Vec<(Polynomial, usize)>> {
+        return self.square_free_factorization_with_tolerance(DEFAULT_GCD_TOLERANCE);
+    }
+    pub fn square_free_factorization_with_tolerance(
+        &self,
+        tolerance: f64,
+    ) -> Result<Vec<(Polynomial, usize)>> {
+        let name = common_variable(&[self])?;
+        let factors = square_free(&self.univariate_coefficients(&name)?, tolerance)?;
+        return Ok(factors
+            .iter()
+            .map(|(f, i)| (Polynomial::from_univariate(&name, f), *i))
+            .collect());
+    }
+    // monic polynomial with the same roots, each of them simple
+    pub fn square_free_part(&self) -> Result<Polynomial> {
+        let factors = self.square_free_factorization()?;
+        return Ok(factors
+            .iter()
+            .fold(Polynomial::constant(1.0), |acc, (f, _)| &acc * f));
+    }
+}
+
+// the only variable of univariate polynomials (empty when they are all constant)
+fn common_variable(polys: &[&Polynomial]) -> Result<String> {
+    let mut variables = polys
+        .iter()
+        .flat_map(|p| p.variables())
+        .collect::<Vec<String>>();
+    variables.sort();
+    variables.dedup();
+    return match variables.len() {
+        0 => Ok(String::new()),
+        1 => Ok(variables.remove(0)),
+        _ => Err(FFError::Polynomial(format!(
+            "expected univariate polynomials in one variable, found variables {:?}",
+            variables
+        ))),
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::product;
+    fn assert_close(a: &Polynomial, b: &Polynomial) {
+        let (a, b) = (a.univariate().unwrap(), b.univariate().unwrap());
+        assert_eq!(a.len(), b.len(), "{:?} != {:?}", a, b);
+        assert!(
+            a.iter().zip(b.iter()).all(|(x, y)| (x - y).abs() < 1e-4),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+    #[test]
+    fn division() {
+        let p = Polynomial::parse("x^3 - 2x^2 - 4").unwrap();
+        let (q, r) = p.div_rem(&Polynomial::parse("x - 3").unwrap()).unwrap();
+        assert_eq!(q, Polynomial::parse("x^2 + x + 3").unwrap());
+        assert_eq!(r, Polynomial::parse("5").unwrap());
+        let (q, r) = p.div_rem(&Polynomial::parse("2").unwrap()).unwrap();
+        assert_eq!(q, Polynomial::parse("0.5x^3 - x^2 - 2").unwrap());
+        assert!(r.is_zero());
+        assert!(p.div_rem(&Polynomial::new()).is_err());
+        assert!(p.div_rem(&Polynomial::parse("y").unwrap()).is_err());
+    }
+    #[test]
+    fn greatest_common_divisor() {
+        let a = product(&["x - 1", "x + 2", "x^2 + 1"]);
+        let b = product(&["2x - 2", "x - 3", "x^2 + 1"]);
+        assert_close(&a.gcd(&b).unwrap(), &product(&["x - 1", "x^2 + 1"]));
+        assert_close(
+            &a.gcd(&Polynomial::parse("x - 5").unwrap()).unwrap(),
+            &Polynomial::constant(1.0),
+        );
+        // f32 rounding of 1.1 leaves a tiny remainder only the tolerant version ignores
+        let a = product(&["x - 1.1", "x + 0.3"]);
+        let b = product(&["x - 1.1", "x - 0.7"]);
+        assert_close(
+            &a.gcd_with_tolerance(&b, 1e-5).unwrap(),
+            &product(&["x - 1.1"]),
+        );
+    }
+    #[test]
+    fn square_free_factorization() {
+        let p = product(&["x - 1", "x - 1", "x - 1", "x + 2", "x + 2", "x - 4", "3"]);
+        let factors = p.square_free_factorization().unwrap();
+        assert_eq!(
+            factors.iter().map(|(_, i)| *i).collect::<Vec<usize>>(),
+            vec![1, 2, 3]
+        );
+        assert_close(&factors[0].0, &product(&["x - 4"]));
+        assert_close(&factors[1].0, &product(&["x + 2"]));
+        assert_close(&factors[2].0, &product(&["x - 1"]));
+        assert_close(
+            &p.square_free_part().unwrap(),
+            &product(&["x - 4", "x + 2", "x - 1"]),
+        );
+        assert!(Polynomial::parse("7")
+            .unwrap()
+            .square_free_factorization()
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -7,6 +7,10 @@ pub enum FFError {
     Op(String),
     Store(String),
     Parse(String),
+    Linalg(String),
+    NonFinite(String),
+    Polynomial(String),
+    LineSearch(String),
 }
 pub type Result<T> = std::result::Result<T, FFError>;
 
@@ -18,6 +22,10 @@ impl Display for FFError {
             FFError::Op(info) => write!(fmt, "op error: {}", info),
             FFError::Store(info) => write!(fmt, "store error: {}", info),
             FFError::Parse(info) => write!(fmt, "parse store: {}", info),
+            FFError::Linalg(info) => write!(fmt, "linalg error: {}", info),
+            FFError::NonFinite(info) => write!(fmt, "non-finite error: {}", info),
+            FFError::Polynomial(info) => write!(fmt, "polynomial error: {}", info),
+            FFError::LineSearch(info) => write!(fmt, "line search error: {}", info),
         }
     }
 }
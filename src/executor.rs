@@ -1,48 +1,56 @@
+use crate::bounds::Bound;
 use crate::error::{FFError, Result};
 use crate::float::Float;
 use crate::graph::Graph;
 use crate::op::Op;
 use crate::ops;
-use crate::optimizer::{AdaGrad, Adam, Optimize, Optimizer, SGD};
+use crate::optimizer::{GradClip, Optimizer, VectorOptimize};
 use crate::store::Store;
 use std::cell::RefCell;
 use std::collections::HashMap;
 pub struct Executor {
     graph: Graph,
     optimizer: Optimizer,
+    grad_clip: Option<GradClip>,
+    check_finite: bool,
+    bounds: HashMap<String, Bound>,
 }
 impl Executor {
     pub fn new(g: Graph, opt: Optimizer) -> Self {
         Executor {
             graph: g,
             optimizer: opt,
+            grad_clip: None,
+            check_finite: false,
+            bounds: HashMap::new(),
         }
     }
+    // constrains a parameter to [lower, upper], set before `init_parameter`
+    // so that the initial value is moved inside the box as well
+    pub fn set_bound(&mut self, name: &str, bound: Bound) {
+        self.bounds.insert(name.to_string(), bound);
+    }
+    pub fn get_bounds(&self) -> &HashMap<String, Bound> {
+        return &self.bounds;
+    }
+    pub fn set_grad_clip(&mut self, clip: Option<GradClip>) {
+        self.grad_clip = clip;
+    }
+    // makes forward/backward fail with `FFError::NonFinite` on NaN or inf values/gradients
+    pub fn set_check_finite(&mut self, check: bool) {
+        self.check_finite = check;
+    }
     pub fn forward(&mut self, s: &mut Store) -> Result<()> {
-        for op in self.graph.get_ops().iter() {
-            match op {
-                Op::Add(r, l, res) => ops::Add::new(*r, *l, *res).forward(s)?,
-                Op::Minus(r, res) => ops::Minus::new(*r, *res).forward(s)?,
-                Op::Mul(r, l, res) => ops::Mul::new(*r, *l, *res).forward(s)?,
-            }
+        forward(&self.graph, s)?;
+        if self.check_finite {
+            check_values(&self.graph, s)?;
         }
         return Ok(());
     }
     pub fn backward(&mut self, s: &mut Store) -> Result<()> {
-        {
-            let output_id = self.graph.get_output();
-            let mut output = s
-                .get(&output_id)
-                .ok_or(FFError::Store(format!("not find id: {}", output_id)))?
-                .borrow_mut();
-            output.grad = 1.0;
-        }
-        for op in self.graph.get_ops().iter().rev() {
-            match op {
-                Op::Add(r, l, res) => ops::Add::new(*r, *l, *res).backward(s)?,
-                Op::Minus(r, res) => ops::Minus::new(*r, *res).backward(s)?,
-                Op::Mul(r, l, res) => ops::Mul::new(*r, *l, *res).backward(s)?,
-            }
+        backward(&self.graph, s)?;
+        if self.check_finite {
+            check_grads(&self.graph, s)?;
         }
         return Ok(());
     }
@@ -51,9 +59,11 @@ impl Executor {
             let value = input_values
                 .get(k)
                 .ok_or(FFError::Store(format!("not find key: {}", k)))?;
-            let mut float_value = Float::default();
-            float_value.is_need_grad = false;
-            float_value.value = *value;
+            let float_value = Float {
+                value: *value,
+                is_need_grad: false,
+                ..Default::default()
+            };
             s.insert(*index, RefCell::new(float_value));
         }
         return Ok(());
@@ -63,29 +73,36 @@ impl Executor {
         input_values: &HashMap<String, f32>,
         s: &mut Store,
     ) -> Result<()> {
+        for bound in self.bounds.values() {
+            bound.validate()?;
+        }
         for (k, index) in self.graph.get_parameters().iter() {
             let value = input_values
                 .get(k)
                 .ok_or(FFError::Store(format!("not find key: {}", k)))?;
-            let mut float_value = Float::default();
-            float_value.is_need_grad = true;
-            float_value.value = *value;
+            let float_value = Float {
+                value: self.bounds.get(k).map_or(*value, |b| b.project(*value)),
+                is_need_grad: true,
+                ..Default::default()
+            };
             s.insert(*index, RefCell::new(float_value));
-            self.optimizer.register(*index);
         }
+        self.optimizer.register(&parameter_indices(&self.graph));
         return Ok(());
     }
     pub fn init_contants(&mut self, s: &mut Store) {
         for (index, value) in self.graph.get_constants().iter() {
-            let mut float_value = Float::default();
-            float_value.is_need_grad = false;
-            float_value.value = *value;
+            let float_value = Float {
+                value: *value,
+                is_need_grad: false,
+                ..Default::default()
+            };
             s.insert(*index, RefCell::new(float_value));
         }
     }
     pub fn clear_grad(&mut self, s: &mut Store) -> Result<()> {
         for (k, index) in self.graph.get_parameters().iter() {
-            s.get(&index)
+            s.get(index)
                 .ok_or(FFError::Store(format!("not find id: {}", k)))?
                 .borrow_mut()
                 .grad = 0.0;
@@ -93,16 +110,56 @@ impl Executor {
         return Ok(());
     }
     pub fn optimize(&mut self, s: &mut Store) -> Result<()> {
-        for (_, index) in self.graph.get_parameters().iter() {
-            let mut f = s
-                .get(index)
-                .ok_or(FFError::Store(format!("not find id: {}", index)))?
-                .borrow_mut();
-            f.value = self.optimizer.optimize(*index, f.value, f.grad);
+        let indices = parameter_indices(&self.graph);
+        let bounds = parameter_bounds(&self.graph, &self.bounds, &indices);
+        let (values, mut grads) = read_parameters(&indices, s)?;
+        // reparameterized parameters are optimized in their unconstrained space
+        let mut values = to_internal(&bounds, &values, &mut grads);
+        if let Some(clip) = &self.grad_clip {
+            clip.clip(&mut grads);
+        }
+        let loss = self.get_output_value(s)?.value;
+        let start = values.clone();
+        let optimized = {
+            let graph = &self.graph;
+            let bounds = &bounds;
+            let grad_clip = &self.grad_clip;
+            let check_finite = self.check_finite;
+            // re-evaluations get the same checks and clipping as the initial gradients,
+            // except that a non-finite loss is left to the line search to back off from
+            let mut objective = |values: &[f32]| {
+                let values = to_external(bounds, values);
+                let (loss, mut grads) = evaluate(graph, &indices, &values, s)?;
+                if check_finite && loss.is_finite() {
+                    check_values(graph, s)?;
+                    check_grads(graph, s)?;
+                }
+                to_internal(bounds, &values, &mut grads);
+                if let Some(clip) = grad_clip {
+                    clip.clip(&mut grads);
+                }
+                return Ok((loss, grads));
+            };
+            self.optimizer
+                .optimize_vector(&indices, &mut values, loss, &grads, &mut objective)
+        };
+        if let Err(e) = optimized {
+            // line searches leave their last trial point in the store
+            for (index, value) in indices.iter().zip(to_external(&bounds, &start)) {
+                get_float(*index, s)?.borrow_mut().value = value;
+            }
+            return Err(e);
+        }
+        for (index, value) in indices.iter().zip(to_external(&bounds, &values)) {
+            get_float(*index, s)?.borrow_mut().value = value;
         }
         return Ok(());
     }
 
+    pub fn step_scheduler(&mut self, loss: f32) {
+        self.optimizer.schedule_step(loss);
+    }
+
     pub fn get_output_value(&self, s: &mut Store) -> Result<Float> {
         let ouput_id = self.graph.get_output();
         return Ok(*(s
@@ -126,20 +183,214 @@ impl Executor {
             .map(|(name, index)| {
                 Ok((
                     name.clone(),
-                    s.get(index)
+                    *s.get(index)
                         .ok_or(FFError::Store(format!("not find id: {}", index)))?
-                        .borrow()
-                        .clone(),
+                        .borrow(),
                 ))
             })
             .collect::<Vec<Result<(String, Float)>>>();
     }
 }
 
+fn forward(graph: &Graph, s: &mut Store) -> Result<()> {
+    for op in graph.get_ops().iter() {
+        match op {
+            Op::Add(r, l, res) => ops::Add::new(*r, *l, *res).forward(s)?,
+            Op::Constraint(method, r, res) => {
+                ops::Elementwise::new(*method, *r, *res).forward(s)?
+            }
+            Op::Loss(loss, r, res) => ops::Elementwise::new(*loss, *r, *res).forward(s)?,
+            Op::Minus(r, res) => ops::Minus::new(*r, *res).forward(s)?,
+            Op::Mul(r, l, res) => ops::Mul::new(*r, *l, *res).forward(s)?,
+        }
+    }
+    return Ok(());
+}
+fn backward(graph: &Graph, s: &mut Store) -> Result<()> {
+    {
+        let output_id = graph.get_output();
+        get_float(output_id, s)?.borrow_mut().grad = 1.0;
+    }
+    for op in graph.get_ops().iter().rev() {
+        match op {
+            Op::Add(r, l, res) => ops::Add::new(*r, *l, *res).backward(s)?,
+            Op::Constraint(method, r, res) => {
+                ops::Elementwise::new(*method, *r, *res).backward(s)?
+            }
+            Op::Loss(loss, r, res) => ops::Elementwise::new(*loss, *r, *res).backward(s)?,
+            Op::Minus(r, res) => ops::Minus::new(*r, *res).backward(s)?,
+            Op::Mul(r, l, res) => ops::Mul::new(*r, *l, *res).backward(s)?,
+        }
+    }
+    return Ok(());
+}
+// human-readable name of a node for error messages
+fn describe_node(graph: &Graph, index: usize) -> String {
+    let named = |map: &HashMap<String, usize>| {
+        map.iter()
+            .find(|(_, i)| **i == index)
+            .map(|(name, _)| name.clone())
+    };
+    if let Some(name) = named(graph.get_parameters()) {
+        return format!("node {} (parameter {})", index, name);
+    }
+    if let Some(name) = named(graph.get_inputs()) {
+        return format!("node {} (input {})", index, name);
+    }
+    for op in graph.get_ops().iter() {
+        match op {
+            Op::Add(r, l, res) if *res == index => {
+                return format!("node {} (add of {} and {})", index, r, l)
+            }
+            Op::Constraint(method, r, res) if *res == index => {
+                return format!("node {} ({:?} of {})", index, method, r)
+            }
+            Op::Loss(loss, r, res) if *res == index => {
+                return format!("node {} ({:?} loss of {})", index, loss, r)
+            }
+            Op::Minus(r, res) if *res == index => {
+                return format!("node {} (minus of {})", index, r)
+            }
+            Op::Mul(r, l, res) if *res == index => {
+                return format!("node {} (mul of {} and {})", index, r, l)
+            }
+            _ => (),
+        }
+    }
+    return format!("node {}", index);
+}
+fn non_finite(graph: &Graph, index: usize, what: &str, value: f32) -> FFError {
+    return FFError::NonFinite(format!(
+        "{} of {} is {}",
+        what,
+        describe_node(graph, index),
+        value
+    ));
+}
+// checks parameters and inputs first, then op results in evaluation order,
+// so the first reported node is where the non-finite value originated
+fn check_values(graph: &Graph, s: &Store) -> Result<()> {
+    let results = graph.get_ops().iter().map(|op| match op {
+        Op::Add(_, _, res)
+        | Op::Constraint(_, _, res)
+        | Op::Loss(_, _, res)
+        | Op::Minus(_, res)
+        | Op::Mul(_, _, res) => *res,
+    });
+    let mut leaves = graph
+        .get_parameters()
+        .values()
+        .chain(graph.get_inputs().values())
+        .copied()
+        .collect::<Vec<usize>>();
+    leaves.sort_unstable();
+    for index in leaves.into_iter().chain(results) {
+        let value = get_float(index, s)?.borrow().value;
+        if !value.is_finite() {
+            return Err(non_finite(graph, index, "value", value));
+        }
+    }
+    return Ok(());
+}
+fn check_grads(graph: &Graph, s: &Store) -> Result<()> {
+    for index in parameter_indices(graph) {
+        let grad = get_float(index, s)?.borrow().grad;
+        if !grad.is_finite() {
+            return Err(non_finite(graph, index, "gradient", grad));
+        }
+    }
+    return Ok(());
+}
+fn get_float(index: usize, s: &Store) -> Result<&RefCell<Float>> {
+    return s
+        .get(&index)
+        .ok_or(FFError::Store(format!("not find id: {}", index)));
+}
+// parameter node indices in a stable order, shared by all vector optimizers
+fn parameter_indices(graph: &Graph) -> Vec<usize> {
+    let mut indices = graph
+        .get_parameters()
+        .values()
+        .copied()
+        .collect::<Vec<usize>>();
+    indices.sort_unstable();
+    return indices;
+}
+fn parameter_bounds(
+    graph: &Graph,
+    bounds: &HashMap<String, Bound>,
+    indices: &[usize],
+) -> Vec<Option<Bound>> {
+    let names = graph
+        .get_parameters()
+        .iter()
+        .map(|(name, index)| (*index, name))
+        .collect::<HashMap<usize, &String>>();
+    return indices
+        .iter()
+        .map(|index| bounds.get(names[index]).copied())
+        .collect();
+}
+// maps parameter values to the optimizer's space and applies the chain rule to `grads`
+fn to_internal(bounds: &[Option<Bound>], values: &[f32], grads: &mut [f32]) -> Vec<f32> {
+    return bounds
+        .iter()
+        .zip(values.iter().zip(grads.iter_mut()))
+        .map(|(bound, (value, grad))| match bound {
+            Some(b) if b.is_reparameterized() => {
+                let u = b.to_internal(*value);
+                *grad *= b.derivative(u);
+                u
+            }
+            _ => *value,
+        })
+        .collect();
+}
+// maps the optimizer's values back to parameters, projecting onto the bounds
+fn to_external(bounds: &[Option<Bound>], values: &[f32]) -> Vec<f32> {
+    return bounds
+        .iter()
+        .zip(values.iter())
+        .map(|(bound, value)| match bound {
+            Some(b) => b.project(b.to_external(*value)),
+            None => *value,
+        })
+        .collect();
+}
+fn read_parameters(indices: &[usize], s: &Store) -> Result<(Vec<f32>, Vec<f32>)> {
+    let mut values = Vec::with_capacity(indices.len());
+    let mut grads = Vec::with_capacity(indices.len());
+    for index in indices.iter() {
+        let f = get_float(*index, s)?.borrow();
+        values.push(f.value);
+        grads.push(f.grad);
+    }
+    return Ok((values, grads));
+}
+// sets the parameters, then reruns forward and backward to get (loss, gradients)
+fn evaluate(
+    graph: &Graph,
+    indices: &[usize],
+    values: &[f32],
+    s: &mut Store,
+) -> Result<(f32, Vec<f32>)> {
+    for (index, value) in indices.iter().zip(values.iter()) {
+        let mut f = get_float(*index, s)?.borrow_mut();
+        f.value = *value;
+        f.grad = 0.0;
+    }
+    forward(graph, s)?;
+    backward(graph, s)?;
+    let loss = get_float(graph.get_output(), s)?.borrow().value;
+    let (_, grads) = read_parameters(indices, s)?;
+    return Ok((loss, grads));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::graph::NodeAllocator;
+    use crate::optimizer::SGD;
     #[test]
     fn executor() {
         let mut allocator = NodeAllocator::new();
@@ -158,11 +409,13 @@ mod tests {
         exec.feed(
             &HashMap::from([("x".to_string(), 0.1), ("y".to_string(), 0.2)]),
             &mut s,
-        );
-        exec.init_parameter(&HashMap::from([("p".to_string(), 0.1)]), &mut s);
+        )
+        .unwrap();
+        exec.init_parameter(&HashMap::from([("p".to_string(), 0.1)]), &mut s)
+            .unwrap();
         exec.init_contants(&mut s);
-        exec.forward(&mut s);
-        exec.backward(&mut s);
+        exec.forward(&mut s).unwrap();
+        exec.backward(&mut s).unwrap();
         let output_float = exec.get_output_value(&mut s).unwrap();
         assert!((output_float.value - (-0.03)).abs() < 0.00001);
         assert!(output_float.grad - 1.0 < 0.00001);
@@ -170,4 +423,145 @@ mod tests {
         assert!(parameter_p.value - 0.1 < 0.00001);
         assert!(parameter_p.grad - (-0.3) < 0.00001);
     }
-}
\ No newline at end of file
+    #[test]
+    fn vector_optimizer() {
+        use crate::optimizer::Objective;
+        // takes a full gradient step only if the re-evaluated loss decreases
+        struct Descent;
+        impl VectorOptimize for Descent {
+            fn optimize_vector(
+                &mut self,
+                _indices: &[usize],
+                values: &mut [f32],
+                loss: f32,
+                grads: &[f32],
+                objective: &mut Objective,
+            ) -> Result<()> {
+                let trial = values
+                    .iter()
+                    .zip(grads.iter())
+                    .map(|(v, g)| v - 0.25 * g)
+                    .collect::<Vec<f32>>();
+                let (trial_loss, _) = objective(&trial)?;
+                if trial_loss < loss {
+                    values.copy_from_slice(&trial);
+                }
+                return Ok(());
+            }
+        }
+        let mut allocator = NodeAllocator::new();
+        // loss = p * p
+        let mut g = Graph::new();
+        let p = g.parameter("p", &mut allocator);
+        let f = g.mul(p, p, &mut allocator);
+        g.output(f);
+
+        let mut s = Store::new();
+        let mut exec = Executor::new(g, Optimizer::Vector(Box::new(Descent)));
+        exec.init_parameter(&HashMap::from([("p".to_string(), 1.0)]), &mut s)
+            .unwrap();
+        exec.init_contants(&mut s);
+        exec.forward(&mut s).unwrap();
+        exec.backward(&mut s).unwrap();
+        exec.optimize(&mut s).unwrap();
+        assert!((exec.get_value(p, &mut s).unwrap().value - 0.5).abs() < 0.00001);
+    }
+    #[test]
+    fn non_finite() {
+        let mut allocator = NodeAllocator::new();
+        // loss = (p * p) * (p * p)
+        let mut g = Graph::new();
+        let p = g.parameter("p", &mut allocator);
+        let p2 = g.mul(p, p, &mut allocator);
+        let p4 = g.mul(p2, p2, &mut allocator);
+        g.output(p4);
+
+        let mut s = Store::new();
+        let mut exec = Executor::new(g, Optimizer::SGD(SGD::new(0.01)));
+        exec.set_check_finite(true);
+        exec.init_parameter(&HashMap::from([("p".to_string(), 1e10)]), &mut s)
+            .unwrap();
+        match exec.forward(&mut s) {
+            Err(FFError::NonFinite(info)) => assert!(info.contains("node 3 (mul of 2 and 2)")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+    #[test]
+    fn objective_checks() {
+        use crate::constraint::ConstraintMethod;
+        use crate::optimizer::{GradClip, Objective};
+        use std::rc::Rc;
+        type Evaluated = Rc<RefCell<Option<Result<(f32, Vec<f32>)>>>>;
+        // evaluates one trial point and keeps what the objective returned
+        struct Probe(f32, Evaluated);
+        impl VectorOptimize for Probe {
+            fn optimize_vector(
+                &mut self,
+                _indices: &[usize],
+                _values: &mut [f32],
+                _loss: f32,
+                _grads: &[f32],
+                objective: &mut Objective,
+            ) -> Result<()> {
+                *self.1.borrow_mut() = Some(objective(&[self.0]));
+                return Ok(());
+            }
+        }
+        let probe = |trial: f32, clip: Option<GradClip>| {
+            let mut allocator = NodeAllocator::new();
+            // loss = -log(-p), its gradient overflows just below 0 while the loss is finite
+            let mut g = Graph::new();
+            let p = g.parameter("p", &mut allocator);
+            let f = g.constraint(p, ConstraintMethod::Barrier, &mut allocator);
+            g.output(f);
+
+            let evaluated: Evaluated = Rc::new(RefCell::new(None));
+            let mut s = Store::new();
+            let optimizer = Optimizer::Vector(Box::new(Probe(trial, evaluated.clone())));
+            let mut exec = Executor::new(g, optimizer);
+            exec.set_check_finite(true);
+            exec.set_grad_clip(clip);
+            exec.init_parameter(&HashMap::from([("p".to_string(), -1.0)]), &mut s)
+                .unwrap();
+            exec.forward(&mut s).unwrap();
+            exec.backward(&mut s).unwrap();
+            exec.optimize(&mut s).unwrap();
+            return evaluated.borrow_mut().take().unwrap();
+        };
+        let (_, grads) = probe(-0.1, Some(GradClip::Value(1.0))).unwrap();
+        assert_eq!(grads, vec![1.0]);
+        match probe(-1e-40, None) {
+            Err(FFError::NonFinite(info)) => assert!(info.contains("parameter p")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+    #[test]
+    fn bounded_parameters() {
+        use crate::bounds::BoundMode;
+        // loss = (p + 1)^2 has its minimum at p = -1, outside of [0, inf)
+        for mode in [BoundMode::Projection, BoundMode::Softplus] {
+            let mut allocator = NodeAllocator::new();
+            let mut g = Graph::new();
+            let p = g.parameter("p", &mut allocator);
+            let one = g.constant(1.0, &mut allocator);
+            let r = g.add(p, one, &mut allocator);
+            let f = g.mul(r, r, &mut allocator);
+            g.output(f);
+
+            let mut s = Store::new();
+            let mut exec = Executor::new(g, Optimizer::SGD(SGD::new(0.1)));
+            exec.set_bound("p", Bound::new(0.0, f32::INFINITY).with_mode(mode));
+            exec.init_parameter(&HashMap::from([("p".to_string(), 2.0)]), &mut s)
+                .unwrap();
+            exec.init_contants(&mut s);
+            for _ in 0..200 {
+                exec.forward(&mut s).unwrap();
+                exec.backward(&mut s).unwrap();
+                exec.optimize(&mut s).unwrap();
+                exec.clear_grad(&mut s).unwrap();
+                assert!(exec.get_value(p, &mut s).unwrap().value >= 0.0);
+            }
+            assert!(exec.get_value(p, &mut s).unwrap().value < 0.05);
+        }
+    }
+}
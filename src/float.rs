@@ -21,6 +21,6 @@ mod tests {
         let f = super::Float::default();
         assert_eq!(f.value, 0.0);
         assert_eq!(f.grad, 0.0);
-        assert_eq!(f.is_need_grad, false);
+        assert!(!f.is_need_grad);
     }
 }
@@ -1,3 +1,5 @@
+use crate::constraint::ConstraintMethod;
+use crate::loss::Loss;
 use crate::op::Op;
 use std::collections::HashMap;
 #[derive(Debug)]
@@ -11,6 +13,11 @@ pub struct Graph {
 pub struct NodeAllocator {
     counter: usize,
 }
+impl Default for NodeAllocator {
+    fn default() -> Self {
+        return NodeAllocator::new();
+    }
+}
 impl NodeAllocator {
     pub fn new() -> Self {
         NodeAllocator { counter: 0 }
@@ -20,6 +27,11 @@ impl NodeAllocator {
         self.counter
     }
 }
+impl Default for Graph {
+    fn default() -> Self {
+        return Graph::new();
+    }
+}
 impl Graph {
     pub fn new() -> Self {
         Graph {
@@ -34,16 +46,13 @@ impl Graph {
         return self.ops.as_ref();
     }
     pub fn get_inputs(&self) -> &HashMap<String, usize> {
-        let ref ref_v = self.inputs;
-        return ref_v;
+        return &self.inputs;
     }
     pub fn get_parameters(&self) -> &HashMap<String, usize> {
-        let ref ref_v = self.parameters;
-        return ref_v;
+        return &self.parameters;
     }
     pub fn get_constants(&self) -> &HashMap<usize, f32> {
-        let ref ref_v = self.constants;
-        return ref_v;
+        return &self.constants;
     }
     pub fn get_output(&self) -> usize {
         return self.output;
@@ -73,6 +82,25 @@ impl Graph {
         self.ops.push(Op::Mul(r, l, res));
         return res;
     }
+    // the squared loss stays a plain multiplication
+    pub fn loss(&mut self, r: usize, loss: Loss, allocator: &mut NodeAllocator) -> usize {
+        if loss == Loss::Squared {
+            return self.mul(r, r, allocator);
+        }
+        let res = allocator.alloc_index();
+        self.ops.push(Op::Loss(loss, r, res));
+        return res;
+    }
+    pub fn constraint(
+        &mut self,
+        r: usize,
+        method: ConstraintMethod,
+        allocator: &mut NodeAllocator,
+    ) -> usize {
+        let res = allocator.alloc_index();
+        self.ops.push(Op::Constraint(method, r, res));
+        return res;
+    }
     pub fn output(&mut self, r: usize) {
         self.output = r;
     }
@@ -83,14 +111,14 @@ impl Graph {
     }
 }
 
-pub fn sum(vs: &Vec<usize>, g: &mut Graph, allocator: &mut NodeAllocator) -> usize {
+pub fn sum(vs: &[usize], g: &mut Graph, allocator: &mut NodeAllocator) -> usize {
     let mut res = vs[0];
     vs.iter()
         .skip(1)
         .for_each(|v| res = g.add(res, *v, allocator));
     return res;
 }
-pub fn mul(vs: &Vec<usize>, g: &mut Graph, allocator: &mut NodeAllocator) -> usize {
+pub fn mul(vs: &[usize], g: &mut Graph, allocator: &mut NodeAllocator) -> usize {
     let mut res = vs[0];
     vs.iter()
         .skip(1)
@@ -113,7 +141,7 @@ pub fn binary_exponentiation_old(
         let mut res = var;
         loop {
             if computed_order * 2 > order_to_compute {
-                order_to_compute = order_to_compute - computed_order;
+                order_to_compute -= computed_order;
                 break;
             }
             res = g.mul(res, res, allocator);
@@ -137,8 +165,7 @@ pub fn binary_exponentiation(
     }
     let mut order_to_compute = order;
     let mut max_power = 1;
-    let mut mem = Vec::new();
-    mem.resize((order as f32).log(2.0) as usize + 1, 0);
+    let mut mem = vec![0; (order as f32).log(2.0) as usize + 1];
     mem[0] = var;
     let mut res = var;
     while order_to_compute != 1 {
@@ -147,9 +174,9 @@ pub fn binary_exponentiation(
         max_power += 1;
         order_to_compute /= 2
     }
-    for offset in 0..max_power - 1 {
+    for (offset, power) in mem.iter().enumerate().take(max_power - 1) {
         if (order >> offset & 1usize) == 1 {
-            res = g.mul(res, mem[offset], allocator);
+            res = g.mul(res, *power, allocator);
         }
     }
     return res;
@@ -171,8 +198,8 @@ mod tests {
         g.output(f);
 
         let ops = g.get_ops();
-        let ref_ops = vec![Op::Add(1, 2, 3), Op::Minus(3, 4), Op::Mul(4, 5, 6)];
-        assert_eq!(ops.iter().zip(ref_ops.iter()).all(|(r, l)| *r == *l), true);
+        let ref_ops = [Op::Add(1, 2, 3), Op::Minus(3, 4), Op::Mul(4, 5, 6)];
+        assert!(ops.iter().zip(ref_ops.iter()).all(|(r, l)| *r == *l));
         let inputs = g.get_inputs();
         assert_eq!(*inputs.get("x").unwrap(), 1);
         assert_eq!(*inputs.get("y").unwrap(), 2);
@@ -0,0 +1,441 @@
+use crate::coefficient::Coefficient;
+use crate::deflation::Deflation;
+use crate::division::cut;
+use crate::error::{FFError, Result};
+use crate::polynomial::{horner, MonomialOrder, Polynomial, Term};
+use crate::rational::Rational;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+// back-substituted univariate coefficients below this (relative) are rounding noise
+const SUBSTITUTION_TOLERANCE: f64 = 1e-9;
+
+// exponents over the basis variables, sorted by name
+type Monomial = Vec<usize>;
+
+// the basis polynomials are kept normalized under the basis order, leading term first
+fn leading(g: &Polynomial<Rational>) -> &Term<Rational> {
+    return &g.get_terms()[0];
+}
+fn monomial(t: &Term<Rational>, names: &[String]) -> Monomial {
+    return names.iter().map(|n| t.degree(n)).collect();
+}
+fn leading_monomial(g: &Polynomial<Rational>, names: &[String]) -> Monomial {
+    return monomial(leading(g), names);
+}
+fn monic(g: &Polynomial<Rational>) -> Polynomial<Rational> {
+    return g.scale(leading(g).get_coefficient().recip());
+}
+// factor * x^shift * g
+fn shifted(
+    g: &Polynomial<Rational>,
+    factor: &Rational,
+    shift: &[usize],
+    names: &[String],
+) -> Polynomial<Rational> {
+    let terms = g
+        .get_terms()
+        .iter()
+        .map(|t| {
+            let mut res = Term::constant(&t.get_coefficient() * factor);
+            for (name, order) in t.get_variables().iter() {
+                res.add_variable(name, *order);
+            }
+            for (name, order) in names.iter().zip(shift.iter()) {
+                if *order > 0 {
+                    res.add_variable(name, *order);
+                }
+            }
+            res
+        })
+        .collect();
+    return Polynomial::from_terms(terms);
+}
+// remainder of the multivariate division by `basis`, no term of it is divisible by a
+// leading monomial of the basis
+fn normal_form(
+    p: &Polynomial<Rational>,
+    basis: &[Polynomial<Rational>],
+    names: &[String],
+    order: MonomialOrder,
+) -> Polynomial<Rational> {
+    let mut p = p.normalized(order);
+    let mut remainder = Vec::new();
+    while let Some(lead) = p.get_terms().first().cloned() {
+        let m = monomial(&lead, names);
+        match basis
+            .iter()
+            .find(|g| divides(&leading_monomial(g, names), &m))
+        {
+            Some(g) => {
+                let shift = quotient(&m, &leading_monomial(g, names));
+                let factor = &lead.get_coefficient() / &leading(g).get_coefficient();
+                p = (&p - &shifted(g, &factor, &shift, names)).normalized(order);
+            }
+            None => {
+                remainder.push(lead);
+                p = Polynomial::from_terms(p.get_terms()[1..].to_vec());
+            }
+        }
+    }
+    return Polynomial::from_terms(remainder);
+}
+fn s_polynomial(
+    a: &Polynomial<Rational>,
+    b: &Polynomial<Rational>,
+    names: &[String],
+    order: MonomialOrder,
+) -> Polynomial<Rational> {
+    let (ma, mb) = (leading_monomial(a, names), leading_monomial(b, names));
+    let l = lcm(&ma, &mb);
+    let one = Rational::one();
+    let a = shifted(&monic(a), &one, &quotient(&l, &ma), names);
+    let b = shifted(&monic(b), &one, &quotient(&l, &mb), names);
+    return (&a - &b).normalized(order);
+}
+// the variables (as indices) the polynomial depends on
+fn support(g: &Polynomial<Rational>, names: &[String]) -> Vec<usize> {
+    return (0..names.len())
+        .filter(|k| g.degree(&names[*k]) > 0)
+        .collect();
+}
+
+fn mul(a: &[usize], b: &[usize]) -> Monomial {
+    return a.iter().zip(b.iter()).map(|(x, y)| x + y).collect();
+}
+fn divides(a: &[usize], b: &[usize]) -> bool {
+    return a.iter().zip(b.iter()).all(|(x, y)| x <= y);
+}
+fn quotient(a: &[usize], b: &[usize]) -> Monomial {
+    return a.iter().zip(b.iter()).map(|(x, y)| x - y).collect();
+}
+fn lcm(a: &[usize], b: &[usize]) -> Monomial {
+    return a.iter().zip(b.iter()).map(|(x, y)| *x.max(y)).collect();
+}
+
+// reduced Groebner basis over the rationals, f32 coefficients are read exactly from
+// their shortest decimal
+#[derive(Debug, Clone)]
+pub struct GroebnerBasis {
+    names: Vec<String>,
+    order: MonomialOrder,
+    basis: Vec<Polynomial<Rational>>,
+}
+impl GroebnerBasis {
+    // Buchberger's algorithm with the product criterion and the normal selection strategy
+    pub fn new<C: Coefficient>(polys: &[Polynomial<C>], order: MonomialOrder) -> Result<Self> {
+        let mut names = polys
+            .iter()
+            .flat_map(|p| p.variables())
+            .collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        let mut basis = Vec::<Polynomial<Rational>>::new();
+        for p in polys.iter() {
+            let p = p.to_exact()?.normalized(order);
+            if !p.is_zero() {
+                basis.push(monic(&p));
+            }
+        }
+        let mut pairs = (0..basis.len())
+            .flat_map(|j| (0..j).map(move |i| (i, j)))
+            .collect::<Vec<(usize, usize)>>();
+        while !pairs.is_empty() {
+            let leads = basis
+                .iter()
+                .map(|g| leading_monomial(g, &names))
+                .collect::<Vec<Monomial>>();
+            // the pair with the smallest lcm of leading monomials first
+            let lcm_of = |(i, j): (usize, usize)| lcm(&leads[i], &leads[j]);
+            let position = (0..pairs.len())
+                .min_by(|a, b| order.compare_exponents(&lcm_of(pairs[*a]), &lcm_of(pairs[*b])))
+                .unwrap();
+            let (i, j) = pairs.swap_remove(position);
+            // coprime leading monomials reduce to zero
+            if lcm(&leads[i], &leads[j]) == mul(&leads[i], &leads[j]) {
+                continue;
+            }
+            let s = s_polynomial(&basis[i], &basis[j], &names, order);
+            let s = normal_form(&s, &basis, &names, order);
+            if s.is_zero() {
+                continue;
+            }
+            basis.push(monic(&s));
+            let k = basis.len() - 1;
+            pairs.extend((0..k).map(|i| (i, k)));
+        }
+        let basis = reduce(basis, &names, order);
+        return Ok(GroebnerBasis {
+            names,
+            order,
+            basis,
+        });
+    }
+    pub fn get_names(&self) -> &[String] {
+        return &self.names;
+    }
+    pub fn get_order(&self) -> MonomialOrder {
+        return self.order;
+    }
+    pub fn len(&self) -> usize {
+        return self.basis.len();
+    }
+    pub fn is_empty(&self) -> bool {
+        return self.basis.is_empty();
+    }
+    // the basis with coefficients rounded to f32
+    pub fn polynomials(&self) -> Vec<Polynomial> {
+        return self.basis.iter().map(|g| g.to_float()).collect();
+    }
+    pub fn exact_polynomials(&self) -> Vec<Polynomial<Rational>> {
+        return self.basis.clone();
+    }
+    // false when the basis is {1}, the equations have no common (complex) solution
+    pub fn is_consistent(&self) -> bool {
+        return !self.basis.iter().any(|g| g.total_degree() == 0);
+    }
+    pub fn normal_form<C: Coefficient>(&self, p: &Polynomial<C>) -> Result<Polynomial<Rational>> {
+        return Ok(normal_form(
+            &self.exact(p)?,
+            &self.basis,
+            &self.names,
+            self.order,
+        ));
+    }
+    // ideal membership, exact: p is a combination of the input equations
+    pub fn contains<C: Coefficient>(&self, p: &Polynomial<C>) -> Result<bool> {
+        return Ok(self.normal_form(p)?.is_zero());
+    }
+    // basis elements in the variables after the first k only, with lex this is the k-th
+    // elimination ideal
+    pub fn elimination(&self, k: usize) -> Vec<Polynomial> {
+        return self
+            .basis
+            .iter()
+            .filter(|g| support(g, &self.names).iter().all(|v| *v >= k))
+            .map(|g| g.to_float())
+            .collect();
+    }
+    // real solutions of a zero-dimensional system from a lex basis: roots of the
+    // univariate polynomial in the last variable, then back substitution one variable
+    // at a time, each step a univariate root finding problem. none for an inconsistent
+    // system
+    pub fn solve_real(&self) -> Result<Vec<HashMap<String, f64>>> {
+        if self.order != MonomialOrder::Lex {
+            return Err(FFError::Polynomial(
+                "triangular solving needs a lex Groebner basis".to_string(),
+            ));
+        }
+        if !self.is_consistent() {
+            return Ok(Vec::new());
+        }
+        let n = self.names.len();
+        let mut partial = vec![vec![0.0; n]];
+        for k in (0..n).rev() {
+            let equations = self
+                .basis
+                .iter()
+                .filter(|g| {
+                    let support = support(g, &self.names);
+                    support.contains(&k) && support.iter().all(|v| *v >= k)
+                })
+                .collect::<Vec<&Polynomial<Rational>>>();
+            if equations.is_empty() {
+                return Err(FFError::Polynomial(format!(
+                    "{} is not determined, the system has infinitely many solutions",
+                    self.names[k]
+                )));
+            }
+            let mut next = Vec::new();
+            for x in partial.iter() {
+                let univariate = equations
+                    .iter()
+                    .map(|g| substitute(g, &self.names, x, k))
+                    .collect::<Vec<Vec<f64>>>();
+                // the lowest degree non-vanishing equation, the others must vanish at its roots
+                let Some(lowest) = univariate
+                    .iter()
+                    .filter(|c| c.len() > 1)
+                    .min_by_key(|c| c.len())
+                else {
+                    if univariate.iter().all(|c| c[0] == 0.0) {
+                        return Err(FFError::Polynomial(format!(
+                            "{} is not determined, every equation vanishes at {:?}",
+                            self.names[k], x
+                        )));
+                    }
+                    continue;
+                };
+                for root in Deflation::new().enumerate(lowest, None)?.iter() {
+                    let residual_ok = univariate.iter().all(|c| {
+                        let magnitude = c
+                            .iter()
+                            .rev()
+                            .fold(0.0, |acc, a| acc * root.value.abs() + a.abs());
+                        horner(c, root.value).abs() <= 1e-6 * magnitude.max(1.0)
+                    });
+                    if residual_ok {
+                        let mut extended = x.clone();
+                        extended[k] = root.value;
+                        next.push(extended);
+                    }
+                }
+            }
+            partial = next;
+        }
+        return Ok(partial
+            .iter()
+            .map(|x| self.names.iter().cloned().zip(x.iter().cloned()).collect())
+            .collect());
+    }
+    fn exact<C: Coefficient>(&self, p: &Polynomial<C>) -> Result<Polynomial<Rational>> {
+        if let Some(name) = p.variables().iter().find(|v| !self.names.contains(v)) {
+            return Err(FFError::Polynomial(format!(
+                "{} is not a variable of the basis",
+                name
+            )));
+        }
+        return p.to_exact();
+    }
+}
+
+// minimal, then fully interreduced basis sorted by increasing leading monomial
+fn reduce(
+    basis: Vec<Polynomial<Rational>>,
+    names: &[String],
+    order: MonomialOrder,
+) -> Vec<Polynomial<Rational>> {
+    let leads = basis
+        .iter()
+        .map(|g| leading_monomial(g, names))
+        .collect::<Vec<Monomial>>();
+    let minimal = (0..basis.len())
+        .filter(|i| {
+            !(0..basis.len()).any(|j| {
+                j != *i && divides(&leads[j], &leads[*i]) && (leads[j] != leads[*i] || j < *i)
+            })
+        })
+        .map(|i| basis[i].clone())
+        .collect::<Vec<Polynomial<Rational>>>();
+    let mut reduced = (0..minimal.len())
+        .map(|i| {
+            let others = minimal
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, g)| g.clone())
+                .collect::<Vec<Polynomial<Rational>>>();
+            let (head, tail) = minimal[i].get_terms().split_first().unwrap();
+            let tail = normal_form(
+                &Polynomial::from_terms(tail.to_vec()),
+                &others,
+                names,
+                order,
+            );
+            let mut terms = vec![head.clone()];
+            terms.extend(tail.get_terms().iter().cloned());
+            monic(&Polynomial::from_terms(terms))
+        })
+        .collect::<Vec<Polynomial<Rational>>>();
+    reduced.sort_by(|a, b| {
+        order.compare_exponents(&leading_monomial(a, names), &leading_monomial(b, names))
+    });
+    return reduced;
+}
+
+// coefficients in variable k (lowest degree first) after plugging in x for variables > k
+fn substitute(g: &Polynomial<Rational>, names: &[String], x: &[f64], k: usize) -> Vec<f64> {
+    let mut c = vec![0.0; g.degree(&names[k]) + 1];
+    for t in g.get_terms().iter() {
+        let value = names
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j > k)
+            .fold(t.get_coefficient().to_f64(), |acc, (j, name)| {
+                acc * x[j].powi(t.degree(name) as i32)
+            });
+        c[t.degree(&names[k])] += value;
+    }
+    let scale = c.iter().fold(0.0, |m: f64, v| m.max(v.abs()));
+    return cut(&c, SUBSTITUTION_TOLERANCE * scale);
+}
+
+// one polynomial per line, `{:#}` for ascii
+impl Display for GroebnerBasis {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        for g in self.basis.iter() {
+            if fmt.alternate() {
+                writeln!(fmt, "{:#}", g)?;
+            } else {
+                writeln!(fmt, "{}", g)?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::system;
+    #[test]
+    fn lex_basis() {
+        let polys = system(&["x^2 + y^2 - 5", "x y - 2"]);
+        let basis = GroebnerBasis::new(&polys, MonomialOrder::Lex).unwrap();
+        assert_eq!(basis.to_string(), "y⁴ − 5y² + 4\n1/2 y³ + x − 5/2 y\n");
+        assert!(basis.is_consistent());
+        assert!(basis.contains(&polys[0]).unwrap());
+        assert!(basis
+            .contains(&Polynomial::parse("x^3 y + x y^3 - 10").unwrap())
+            .unwrap());
+        assert!(!basis
+            .contains(&Polynomial::parse("x - y").unwrap())
+            .unwrap());
+        assert_eq!(basis.elimination(1).len(), 1);
+        let mut solutions = basis
+            .solve_real()
+            .unwrap()
+            .iter()
+            .map(|s| (s["x"], s["y"]))
+            .collect::<Vec<(f64, f64)>>();
+        solutions.sort_by(|a, b| a.0.total_cmp(&b.0));
+        assert_eq!(solutions.len(), 4);
+        for (s, e) in solutions
+            .iter()
+            .zip([(-2.0, -1.0), (-1.0, -2.0), (1.0, 2.0), (2.0, 1.0)])
+        {
+            assert!(
+                (s.0 - e.0).abs() < 1e-9 && (s.1 - e.1).abs() < 1e-9,
+                "{:?}",
+                s
+            );
+        }
+    }
+    #[test]
+    fn orders_and_consistency() {
+        let polys = system(&["x^2 - y", "x y - 0.1"]);
+        for order in [
+            MonomialOrder::Lex,
+            MonomialOrder::GrLex,
+            MonomialOrder::GRevLex,
+        ] {
+            let basis = GroebnerBasis::new(&polys, order).unwrap();
+            assert!(polys.iter().all(|p| basis.contains(p).unwrap()));
+        }
+        // y^3 = 1/100 exactly, only possible with rational coefficients
+        let lex = GroebnerBasis::new(&polys, MonomialOrder::Lex).unwrap();
+        assert!(lex.to_string().starts_with("y³ − 1/100\n"));
+        let inconsistent =
+            GroebnerBasis::new(&system(&["x + y - 1", "x + y - 2"]), MonomialOrder::GRevLex)
+                .unwrap();
+        assert!(!inconsistent.is_consistent());
+        assert!(inconsistent.solve_real().is_err());
+        let inconsistent =
+            GroebnerBasis::new(&system(&["x - 1", "x - 2"]), MonomialOrder::Lex).unwrap();
+        assert_eq!(inconsistent.solve_real().unwrap(), vec![]);
+        // x y - y vanishes for every x once y = 0
+        let line =
+            GroebnerBasis::new(&system(&["x y - y", "y^2 - y"]), MonomialOrder::Lex).unwrap();
+        assert!(line.solve_real().is_err());
+    }
+}
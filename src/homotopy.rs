@@ -0,0 +1,488 @@
+use crate::complex::Complex;
+use crate::error::{FFError, Result};
+use crate::polynomial::Polynomial;
+use std::collections::HashMap;
+
+// paths whose iterate grows beyond this norm head to a solution at infinity
+const DIVERGENCE: f64 = 1e8;
+// step size failures this close to t = 1 end at a (likely singular) solution
+const ENDGAME: f64 = 1e-3;
+// tracking stops this close to t = 1, the last stretch is left to Newton on the target
+// system so that paths to infinity cannot jump onto a finite solution
+const FINAL: f64 = 1e-12;
+// endpoints with larger residuals (relative to the size of the terms) are not solutions
+const RESIDUAL_TOLERANCE: f64 = 1e-8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HomotopySolution {
+    // in the order of `HomotopyResult::names`
+    pub values: Vec<Complex>,
+    // largest |f_i| at the solution
+    pub residual: f64,
+    pub is_real: bool,
+    // paths that ended at this solution, more than one for singular solutions
+    pub multiplicity: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct HomotopyResult {
+    pub names: Vec<String>,
+    pub solutions: Vec<HomotopySolution>,
+    // number of paths tracked, the total degree of the system
+    pub paths: usize,
+    // paths that went to infinity
+    pub diverged: usize,
+    // paths where the step size underflowed before the endgame
+    pub failed: usize,
+}
+impl HomotopyResult {
+    pub fn real_solutions(&self) -> Vec<HashMap<String, f64>> {
+        return self
+            .solutions
+            .iter()
+            .filter(|s| s.is_real)
+            .map(|s| {
+                self.names
+                    .iter()
+                    .cloned()
+                    .zip(s.values.iter().map(|v| v.re))
+                    .collect()
+            })
+            .collect();
+    }
+}
+
+enum PathEnd {
+    Finite(Vec<Complex>),
+    Diverged,
+    Failed,
+}
+
+// total-degree homotopy H(x, t) = (1 - t) gamma g(x) + t f(x) with start system
+// g_i = x_i^d_i - 1, tracked by an RK4 predictor and Newton corrector in complex f64
+pub struct Homotopy {
+    gamma: Complex,
+    initial_step: f64,
+    min_step: f64,
+    corrector_iterations: usize,
+    tolerance: f64,
+    real_tolerance: f64,
+}
+impl Homotopy {
+    pub fn new() -> Self {
+        Homotopy {
+            gamma: Complex::from_polar(1.0, 2.1093),
+            initial_step: 0.01,
+            min_step: 1e-12,
+            corrector_iterations: 3,
+            tolerance: 1e-10,
+            real_tolerance: 1e-8,
+        }
+    }
+    // the constant of the gamma trick, paths stay regular for all but finitely many angles
+    pub fn with_gamma(mut self, gamma: Complex) -> Self {
+        self.gamma = gamma;
+        return self;
+    }
+    pub fn with_step(mut self, initial_step: f64, min_step: f64) -> Self {
+        self.initial_step = initial_step;
+        self.min_step = min_step;
+        return self;
+    }
+    // Newton corrections below `tolerance` (relative to the point) count as converged
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        return self;
+    }
+    // solutions with every imaginary part below this (relative to the value) are real
+    pub fn with_real_tolerance(mut self, real_tolerance: f64) -> Self {
+        self.real_tolerance = real_tolerance;
+        return self;
+    }
+    pub fn solve(&self, polys: &[Polynomial]) -> Result<HomotopyResult> {
+        let system = System::new(polys)?;
+        let mut ends = Vec::new();
+        let (mut diverged, mut failed) = (0, 0);
+        let starts = system.start_solutions();
+        for start in starts.iter() {
+            match self.track(&system, start.clone()) {
+                PathEnd::Finite(x) => {
+                    let x = self.refine(&system, x);
+                    // an endpoint that does not solve the system is on its way to infinity
+                    match system.is_solution(&x) {
+                        true => ends.push(x),
+                        false => diverged += 1,
+                    }
+                }
+                PathEnd::Diverged => diverged += 1,
+                PathEnd::Failed => failed += 1,
+            }
+        }
+        let mut solutions = Vec::<HomotopySolution>::new();
+        for x in ends.into_iter() {
+            let same = |s: &&mut HomotopySolution| {
+                s.values
+                    .iter()
+                    .zip(x.iter())
+                    .all(|(a, b)| (*a - *b).norm() <= 1e-6 * (1.0 + b.norm()))
+            };
+            match solutions.iter_mut().find(same) {
+                Some(s) => s.multiplicity += 1,
+                None => {
+                    let residual = system
+                        .evaluate(&x)
+                        .iter()
+                        .fold(0.0, |m: f64, v| m.max(v.norm()));
+                    let is_real = x
+                        .iter()
+                        .all(|v| v.im.abs() <= self.real_tolerance * (1.0 + v.norm()));
+                    solutions.push(HomotopySolution {
+                        values: x,
+                        residual,
+                        is_real,
+                        multiplicity: 1,
+                    });
+                }
+            }
+        }
+        return Ok(HomotopyResult {
+            names: system.names.clone(),
+            solutions,
+            paths: starts.len(),
+            diverged,
+            failed,
+        });
+    }
+    fn track(&self, system: &System, start: Vec<Complex>) -> PathEnd {
+        let mut x = start;
+        let (mut t, mut h) = (0.0, self.initial_step);
+        let mut successes = 0;
+        while 1.0 - t > FINAL {
+            // approach t = 1 geometrically, diverging paths blow up on the way
+            h = h.min(0.5 * (1.0 - t));
+            let next = self
+                .predict(system, &x, t, h)
+                .and_then(|p| self.correct(system, p, t + h));
+            match next {
+                Some(corrected) => {
+                    x = corrected;
+                    t += h;
+                    successes += 1;
+                    if successes >= 3 {
+                        h *= 2.0;
+                        successes = 0;
+                    }
+                }
+                None => {
+                    h *= 0.5;
+                    successes = 0;
+                    if h < self.min_step {
+                        return match 1.0 - t <= ENDGAME {
+                            true => PathEnd::Finite(x),
+                            false => PathEnd::Failed,
+                        };
+                    }
+                }
+            }
+            if norm(&x) > DIVERGENCE {
+                return PathEnd::Diverged;
+            }
+        }
+        return PathEnd::Finite(x);
+    }
+    // one RK4 step of dx/dt = -H_x^-1 H_t
+    fn predict(&self, system: &System, x: &[Complex], t: f64, h: f64) -> Option<Vec<Complex>> {
+        let velocity = |x: &[Complex], t: f64| {
+            let (_, jacobian, dt) = system.homotopy(x, t, self.gamma);
+            let minus_dt = dt.iter().map(|v| -*v).collect::<Vec<Complex>>();
+            return solve_complex(jacobian, minus_dt);
+        };
+        let shifted = |k: &[Complex], scale: f64| {
+            x.iter()
+                .zip(k.iter())
+                .map(|(a, b)| *a + *b * scale)
+                .collect::<Vec<Complex>>()
+        };
+        let k1 = velocity(x, t)?;
+        let k2 = velocity(&shifted(&k1, 0.5 * h), t + 0.5 * h)?;
+        let k3 = velocity(&shifted(&k2, 0.5 * h), t + 0.5 * h)?;
+        let k4 = velocity(&shifted(&k3, h), t + h)?;
+        return Some(
+            (0..x.len())
+                .map(|i| x[i] + (k1[i] + k2[i] * 2.0 + k3[i] * 2.0 + k4[i]) * (h / 6.0))
+                .collect(),
+        );
+    }
+    // Newton on H(., t), None when it does not converge within a few iterations
+    fn correct(&self, system: &System, mut x: Vec<Complex>, t: f64) -> Option<Vec<Complex>> {
+        for _ in 0..self.corrector_iterations {
+            let (value, jacobian, _) = system.homotopy(&x, t, self.gamma);
+            let dx = solve_complex(jacobian, value.iter().map(|v| -*v).collect())?;
+            x.iter_mut().zip(dx.iter()).for_each(|(a, b)| *a += *b);
+            if norm(&dx) <= self.tolerance * (1.0 + norm(&x)) {
+                return Some(x);
+            }
+        }
+        return None;
+    }
+    // Newton on the target system at t = 1, kept only while the residual improves
+    fn refine(&self, system: &System, mut x: Vec<Complex>) -> Vec<Complex> {
+        let residual = |x: &[Complex]| norm(&system.evaluate(x));
+        for _ in 0..10 {
+            let (value, jacobian, _) = system.homotopy(&x, 1.0, self.gamma);
+            let dx = match solve_complex(jacobian, value.iter().map(|v| -*v).collect()) {
+                Some(dx) => dx,
+                None => break,
+            };
+            let next = x
+                .iter()
+                .zip(dx.iter())
+                .map(|(a, b)| *a + *b)
+                .collect::<Vec<Complex>>();
+            if residual(&next) >= residual(&x) {
+                break;
+            }
+            x = next;
+        }
+        return x;
+    }
+}
+impl Default for Homotopy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// terms as (coefficient, [(variable index, order)]) for fast complex evaluation
+type Compiled = Vec<(f64, Vec<(usize, usize)>)>;
+
+struct System {
+    names: Vec<String>,
+    degrees: Vec<usize>,
+    polys: Vec<Compiled>,
+    // jacobian[i][j] = d f_i / d x_j
+    jacobian: Vec<Vec<Compiled>>,
+}
+impl System {
+    fn new(polys: &[Polynomial]) -> Result<Self> {
+        let mut names = polys
+            .iter()
+            .flat_map(|p| p.variables())
+            .collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        if names.len() != polys.len() || names.is_empty() {
+            return Err(FFError::Polynomial(format!(
+                "homotopy continuation needs a square system, got {} equations in {} unknowns",
+                polys.len(),
+                names.len()
+            )));
+        }
+        let compile = |p: &Polynomial| {
+            p.get_terms()
+                .iter()
+                .map(|t| {
+                    let variables = t
+                        .get_variables()
+                        .iter()
+                        .filter(|(_, order)| **order > 0)
+                        .map(|(name, order)| {
+                            (names.iter().position(|n| n == name).unwrap(), *order)
+                        })
+                        .collect();
+                    (t.get_coefficient() as f64, variables)
+                })
+                .collect::<Compiled>()
+        };
+        let mut degrees = Vec::new();
+        for p in polys.iter() {
+            let degree = p.total_degree();
+            if degree == 0 {
+                return Err(FFError::Polynomial(format!("constant equation {} = 0", p)));
+            }
+            degrees.push(degree);
+        }
+        return Ok(System {
+            degrees,
+            polys: polys.iter().map(compile).collect(),
+            jacobian: polys
+                .iter()
+                .map(|p| {
+                    names
+                        .iter()
+                        .map(|name| compile(&p.derivative(name)))
+                        .collect()
+                })
+                .collect(),
+            names,
+        });
+    }
+    // every combination of d_i-th roots of unity solves the start system
+    fn start_solutions(&self) -> Vec<Vec<Complex>> {
+        let mut starts = vec![Vec::new()];
+        for d in self.degrees.iter() {
+            let roots = (0..*d)
+                .map(|k| {
+                    Complex::from_polar(1.0, 2.0 * std::f64::consts::PI * k as f64 / *d as f64)
+                })
+                .collect::<Vec<Complex>>();
+            starts = starts
+                .iter()
+                .flat_map(|s| {
+                    roots.iter().map(move |r| {
+                        let mut next = s.clone();
+                        next.push(*r);
+                        next
+                    })
+                })
+                .collect();
+        }
+        return starts;
+    }
+    fn evaluate(&self, x: &[Complex]) -> Vec<Complex> {
+        return self.polys.iter().map(|p| evaluate(p, x)).collect();
+    }
+    // every |f_i(x)| is small next to the sum of the magnitudes of its terms
+    fn is_solution(&self, x: &[Complex]) -> bool {
+        let magnitudes = x
+            .iter()
+            .map(|v| Complex::from_real(v.norm()))
+            .collect::<Vec<Complex>>();
+        return self.polys.iter().all(|p| {
+            let absolute = p
+                .iter()
+                .map(|(c, v)| (c.abs(), v.clone()))
+                .collect::<Compiled>();
+            evaluate(p, x).norm() <= RESIDUAL_TOLERANCE * evaluate(&absolute, &magnitudes).re
+        });
+    }
+    // H(x, t), H_x(x, t) and H_t(x, t)
+    fn homotopy(
+        &self,
+        x: &[Complex],
+        t: f64,
+        gamma: Complex,
+    ) -> (Vec<Complex>, Vec<Vec<Complex>>, Vec<Complex>) {
+        let n = x.len();
+        let s = gamma * (1.0 - t);
+        let mut value = Vec::with_capacity(n);
+        let mut jacobian = Vec::with_capacity(n);
+        let mut dt = Vec::with_capacity(n);
+        for i in 0..n {
+            let d = self.degrees[i] as i32;
+            let f = evaluate(&self.polys[i], x);
+            let g = x[i].powi(d) - 1.0;
+            value.push(s * g + f * t);
+            dt.push(f - gamma * g);
+            let row = (0..n)
+                .map(|j| {
+                    let df = evaluate(&self.jacobian[i][j], x) * t;
+                    match i == j {
+                        true => df + s * x[i].powi(d - 1) * d as f64,
+                        false => df,
+                    }
+                })
+                .collect();
+            jacobian.push(row);
+        }
+        return (value, jacobian, dt);
+    }
+}
+
+fn evaluate(p: &Compiled, x: &[Complex]) -> Complex {
+    return p.iter().fold(Complex::zero(), |acc, (c, variables)| {
+        acc + variables
+            .iter()
+            .fold(Complex::from_real(*c), |m, (j, order)| {
+                m * x[*j].powi(*order as i32)
+            })
+    });
+}
+
+fn norm(x: &[Complex]) -> f64 {
+    return x.iter().map(|v| v.norm_sqr()).sum::<f64>().sqrt();
+}
+
+// Gaussian elimination with partial pivoting, None for a (numerically) singular matrix
+fn solve_complex(mut a: Vec<Vec<Complex>>, mut b: Vec<Complex>) -> Option<Vec<Complex>> {
+    let n = b.len();
+    let scale = a.iter().flatten().fold(0.0, |m: f64, v| m.max(v.norm()));
+    for k in 0..n {
+        let pivot = (k..n).max_by(|i, j| a[*i][k].norm().total_cmp(&a[*j][k].norm()))?;
+        if a[pivot][k].norm() <= 1e-14 * scale || !a[pivot][k].is_finite() {
+            return None;
+        }
+        a.swap(k, pivot);
+        b.swap(k, pivot);
+        for i in k + 1..n {
+            let factor = a[i][k] / a[k][k];
+            let row = a[k].clone();
+            for (target, v) in a[i].iter_mut().zip(row.iter()).skip(k) {
+                *target -= factor * *v;
+            }
+            let v = b[k];
+            b[i] -= factor * v;
+        }
+    }
+    for i in (0..n).rev() {
+        let sum = (i + 1..n).fold(Complex::zero(), |acc, j| acc + a[i][j] * b[j]);
+        b[i] = (b[i] - sum) / a[i][i];
+    }
+    return Some(b);
+}
+
+impl Polynomial {
+    // all isolated solutions of a square system by total-degree homotopy continuation
+    pub fn solve_system(polys: &[Polynomial]) -> Result<HomotopyResult> {
+        return Homotopy::new().solve(polys);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::system;
+    #[test]
+    fn real_solutions() {
+        let result = Polynomial::solve_system(&system(&["x^2 + y^2 - 5", "x y - 2"])).unwrap();
+        assert_eq!(result.paths, 4);
+        assert_eq!(result.solutions.len(), 4);
+        assert!(result
+            .solutions
+            .iter()
+            .all(|s| s.is_real && s.residual < 1e-9));
+        let mut real = result
+            .real_solutions()
+            .iter()
+            .map(|s| (s["x"], s["y"]))
+            .collect::<Vec<(f64, f64)>>();
+        real.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (s, e) in real
+            .iter()
+            .zip([(-2.0, -1.0), (-1.0, -2.0), (1.0, 2.0), (2.0, 1.0)])
+        {
+            assert!(
+                (s.0 - e.0).abs() < 1e-8 && (s.1 - e.1).abs() < 1e-8,
+                "{:?}",
+                s
+            );
+        }
+    }
+    #[test]
+    fn complex_and_infinite_solutions() {
+        // y = -1 +- i/sqrt(2), x = y + 2
+        let result = Polynomial::solve_system(&system(&["x^2 + y^2 - 1", "x - y - 2"])).unwrap();
+        assert_eq!(result.solutions.len(), 2);
+        assert!(result
+            .solutions
+            .iter()
+            .all(|s| !s.is_real && s.residual < 1e-9));
+        // x y = 1 meets x = 2 once, the other path goes to infinity
+        let result = Polynomial::solve_system(&system(&["x y - 1", "x - 2"])).unwrap();
+        assert_eq!(result.solutions.len(), 1);
+        assert_eq!(result.diverged + result.failed, 1);
+        assert!(result.solutions[0].is_real);
+        assert!((result.solutions[0].values[1].re - 0.5).abs() < 1e-9);
+        assert!(Polynomial::solve_system(&system(&["x + y"])).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+use crate::error::{FFError, Result};
+use crate::optimizer::{Objective, VectorOptimize};
+use std::collections::VecDeque;
+
+//L-BFGS optimizer
+pub struct LBFGS {
+    history_size: usize,
+    line_search: LineSearch,
+    // (s, y, 1 / y's) pairs, oldest first
+    history: VecDeque<(Vec<f64>, Vec<f64>, f64)>,
+}
+impl LBFGS {
+    pub fn new(history_size: usize) -> Self {
+        LBFGS {
+            history_size: history_size.max(1),
+            line_search: LineSearch::default(),
+            history: VecDeque::new(),
+        }
+    }
+    pub fn with_line_search(mut self, line_search: LineSearch) -> Self {
+        self.line_search = line_search;
+        return self;
+    }
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+    // two-loop recursion: returns -H * g
+    fn direction(&self, g: &[f64]) -> Vec<f64> {
+        let mut q = g.to_vec();
+        let mut alphas = Vec::with_capacity(self.history.len());
+        for (s, y, rho) in self.history.iter().rev() {
+            let alpha = rho * dot(s, &q);
+            axpy(-alpha, y, &mut q);
+            alphas.push(alpha);
+        }
+        if let Some((s, y, _)) = self.history.back() {
+            let gamma = dot(s, y) / dot(y, y);
+            q.iter_mut().for_each(|v| *v *= gamma);
+        }
+        for ((s, y, rho), alpha) in self.history.iter().zip(alphas.iter().rev()) {
+            let beta = rho * dot(y, &q);
+            axpy(alpha - beta, s, &mut q);
+        }
+        q.iter_mut().for_each(|v| *v = -*v);
+        return q;
+    }
+}
+impl VectorOptimize for LBFGS {
+    fn register(&mut self, _indices: &[usize]) {
+        self.reset();
+    }
+    fn optimize_vector(
+        &mut self,
+        _indices: &[usize],
+        values: &mut [f32],
+        loss: f32,
+        grads: &[f32],
+        objective: &mut Objective,
+    ) -> Result<()> {
+        let x = values.iter().map(|v| *v as f64).collect::<Vec<f64>>();
+        let g = grads.iter().map(|v| *v as f64).collect::<Vec<f64>>();
+        if norm(&g) == 0.0 {
+            return Ok(());
+        }
+        let (d, alpha, new_g) = loop {
+            let mut d = self.direction(&g);
+            if dot(&d, &g) >= 0.0 {
+                // not a descent direction, fall back to steepest descent
+                self.reset();
+                d = g.iter().map(|v| -v).collect();
+            }
+            let alpha0 = if self.history.is_empty() {
+                (1.0 / norm(&g)).min(1.0)
+            } else {
+                1.0
+            };
+            let found = self
+                .line_search
+                .search(&x, loss as f64, &g, &d, alpha0, objective)?;
+            match found {
+                Some((alpha, _, new_g)) => break (d, alpha, new_g),
+                // retry once along steepest descent before giving up
+                None if !self.history.is_empty() => self.reset(),
+                None => {
+                    return Err(FFError::LineSearch(format!(
+                        "no step satisfying the strong Wolfe conditions at loss {}",
+                        loss
+                    )))
+                }
+            }
+        };
+        let s = d.iter().map(|v| alpha * v).collect::<Vec<f64>>();
+        let y = new_g
+            .iter()
+            .zip(g.iter())
+            .map(|(n, o)| n - o)
+            .collect::<Vec<f64>>();
+        let sy = dot(&s, &y);
+        if sy > 1e-12 {
+            if self.history.len() == self.history_size {
+                self.history.pop_front();
+            }
+            self.history.push_back((s.clone(), y, 1.0 / sy));
+        }
+        for (v, (x, s)) in values.iter_mut().zip(x.iter().zip(s.iter())) {
+            *v = (x + s) as f32;
+        }
+        return Ok(());
+    }
+}
+
+// strong-Wolfe line search (Nocedal & Wright, algorithms 3.5 and 3.6)
+#[derive(Debug, Clone, Copy)]
+pub struct LineSearch {
+    pub c1: f64,
+    pub c2: f64,
+    pub max_step: f64,
+    pub max_iterations: usize,
+}
+impl Default for LineSearch {
+    fn default() -> Self {
+        LineSearch {
+            c1: 1e-4,
+            c2: 0.9,
+            max_step: 1e10,
+            max_iterations: 20,
+        }
+    }
+}
+// a trial point along the search direction: (step, loss, gradient, directional derivative)
+type Trial = (f64, f64, Vec<f64>, f64);
+impl LineSearch {
+    // returns (step, loss, gradient) at a point satisfying the strong Wolfe conditions,
+    // or `None` if no such point was found within `max_iterations` evaluations
+    pub fn search(
+        &self,
+        x: &[f64],
+        f0: f64,
+        g0: &[f64],
+        d: &[f64],
+        alpha0: f64,
+        objective: &mut Objective,
+    ) -> Result<Option<(f64, f64, Vec<f64>)>> {
+        let dphi0 = dot(g0, d);
+        if dphi0 >= 0.0 {
+            return Ok(None);
+        }
+        let mut evaluate = |alpha: f64| -> Result<Trial> {
+            let point = x
+                .iter()
+                .zip(d.iter())
+                .map(|(x, d)| (x + alpha * d) as f32)
+                .collect::<Vec<f32>>();
+            let (f, g) = objective(&point)?;
+            let g = g.iter().map(|v| *v as f64).collect::<Vec<f64>>();
+            let dphi = dot(&g, d);
+            return Ok((alpha, f as f64, g, dphi));
+        };
+        let mut prev: Trial = (0.0, f0, g0.to_vec(), dphi0);
+        let mut alpha = alpha0.min(self.max_step);
+        for i in 0..self.max_iterations {
+            let trial = evaluate(alpha)?;
+            if !trial.1.is_finite()
+                || trial.1 > f0 + self.c1 * alpha * dphi0
+                || (i > 0 && trial.1 >= prev.1)
+            {
+                return self.zoom(prev, trial, f0, dphi0, &mut evaluate);
+            }
+            if trial.3.abs() <= -self.c2 * dphi0 {
+                return Ok(Some((trial.0, trial.1, trial.2)));
+            }
+            if trial.3 >= 0.0 {
+                return self.zoom(trial, prev, f0, dphi0, &mut evaluate);
+            }
+            prev = trial;
+            if alpha >= self.max_step {
+                break;
+            }
+            alpha = (2.0 * alpha).min(self.max_step);
+        }
+        return Ok(None);
+    }
+    fn zoom(
+        &self,
+        mut lo: Trial,
+        mut hi: Trial,
+        f0: f64,
+        dphi0: f64,
+        evaluate: &mut dyn FnMut(f64) -> Result<Trial>,
+    ) -> Result<Option<(f64, f64, Vec<f64>)>> {
+        for _ in 0..self.max_iterations {
+            let alpha = interpolate(&lo, &hi);
+            let trial = evaluate(alpha)?;
+            if !trial.1.is_finite() || trial.1 > f0 + self.c1 * alpha * dphi0 || trial.1 >= lo.1 {
+                hi = trial;
+            } else {
+                if trial.3.abs() <= -self.c2 * dphi0 {
+                    return Ok(Some((trial.0, trial.1, trial.2)));
+                }
+                if trial.3 * (hi.0 - lo.0) >= 0.0 {
+                    hi = lo;
+                }
+                lo = trial;
+            }
+            if (hi.0 - lo.0).abs() < 1e-12 {
+                break;
+            }
+        }
+        // settle for sufficient decrease if the curvature condition could not be met
+        if lo.0 > 0.0 && lo.1 < f0 {
+            return Ok(Some((lo.0, lo.1, lo.2)));
+        }
+        return Ok(None);
+    }
+}
+
+// minimizer of the cubic through both trial points, safeguarded towards bisection
+fn interpolate(lo: &Trial, hi: &Trial) -> f64 {
+    let (a, fa, _, da) = lo;
+    let (b, fb, _, db) = hi;
+    let (left, right) = if a < b { (*a, *b) } else { (*b, *a) };
+    let bisection = 0.5 * (a + b);
+    if !fb.is_finite() {
+        return bisection;
+    }
+    let d1 = da + db - 3.0 * (fa - fb) / (a - b);
+    let disc = d1 * d1 - da * db;
+    if disc < 0.0 {
+        return bisection;
+    }
+    let d2 = (b - a).signum() * disc.sqrt();
+    let alpha = b - (b - a) * (db + d2 - d1) / (db - da + 2.0 * d2);
+    let margin = 0.1 * (right - left);
+    if !alpha.is_finite() || alpha < left + margin || alpha > right - margin {
+        return bisection;
+    }
+    return alpha;
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    return a.iter().zip(b.iter()).map(|(a, b)| a * b).sum();
+}
+fn norm(a: &[f64]) -> f64 {
+    return dot(a, a).sqrt();
+}
+fn axpy(alpha: f64, x: &[f64], y: &mut [f64]) {
+    y.iter_mut()
+        .zip(x.iter())
+        .for_each(|(y, x)| *y += alpha * x);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    // rosenbrock function (1 - a)^2 + 100 (b - a^2)^2
+    fn rosenbrock(v: &[f32]) -> Result<(f32, Vec<f32>)> {
+        let (a, b) = (v[0] as f64, v[1] as f64);
+        let f = (1.0 - a).powi(2) + 100.0 * (b - a * a).powi(2);
+        let ga = -2.0 * (1.0 - a) - 400.0 * a * (b - a * a);
+        let gb = 200.0 * (b - a * a);
+        return Ok((f as f32, vec![ga as f32, gb as f32]));
+    }
+    #[test]
+    fn lbfgs_rosenbrock() {
+        let mut opt = LBFGS::new(5);
+        let indices = [1, 2];
+        let mut values = vec![-1.2, 1.0];
+        opt.register(&indices);
+        for _ in 0..100 {
+            let (loss, grads) = rosenbrock(&values).unwrap();
+            opt.optimize_vector(&indices, &mut values, loss, &grads, &mut rosenbrock)
+                .unwrap();
+        }
+        assert!((values[0] - 1.0).abs() < 1e-2);
+        assert!((values[1] - 1.0).abs() < 1e-2);
+    }
+    #[test]
+    fn strong_wolfe() {
+        let search = LineSearch::default();
+        let mut quadratic = |v: &[f32]| -> Result<(f32, Vec<f32>)> {
+            return Ok((v[0] * v[0], vec![2.0 * v[0]]));
+        };
+        let (alpha, f, g) = search
+            .search(&[2.0], 4.0, &[4.0], &[-4.0], 1.0, &mut quadratic)
+            .unwrap()
+            .unwrap();
+        assert!(f <= 4.0 + search.c1 * alpha * -16.0);
+        assert!((g[0] * -4.0).abs() <= search.c2 * 16.0);
+    }
+}
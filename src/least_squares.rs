@@ -0,0 +1,355 @@
+use crate::bounds::Bound;
+use crate::error::{FFError, Result};
+use crate::executor::Executor;
+use crate::float::Float;
+use crate::graph::NodeAllocator;
+use crate::linalg::{self, Matrix};
+use crate::optimizer::{Optimizer, SGD};
+use crate::polynomial::Polynomial;
+use crate::store::Store;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Method {
+    // solves J dx = -r, square systems only
+    Newton,
+    // solves min |J dx + r|
+    GaussNewton,
+    // solves (J'J + lambda diag(J'J)) dx = -J'r, adapting lambda
+    LevenbergMarquardt,
+}
+
+// residual vector and jacobian of a polynomial system, evaluated on the graph engine
+pub struct ResidualSystem {
+    execs: Vec<Executor>,
+    names: Vec<String>,
+    indices: Vec<usize>,
+    store: Store,
+}
+impl ResidualSystem {
+    pub fn new(polys: &[Polynomial]) -> Result<Self> {
+        let mut allocator = NodeAllocator::new();
+        let mut name_record = HashMap::<String, usize>::new();
+        // residual graphs are never optimized, the optimizer is only a placeholder
+        let mut execs = polys
+            .iter()
+            .map(|poly| {
+                let graph = poly.tranform_to_residual_graph(&mut name_record, &mut allocator);
+                return Executor::new(graph, Optimizer::SGD(SGD::new(0.0)));
+            })
+            .collect::<Vec<Executor>>();
+        let mut names = name_record.keys().cloned().collect::<Vec<String>>();
+        names.sort();
+        let indices = names.iter().map(|name| name_record[name]).collect();
+        let zeros = names
+            .iter()
+            .map(|name| (name.clone(), 0.0))
+            .collect::<HashMap<String, f32>>();
+        let mut store = Store::new();
+        for exec in execs.iter_mut() {
+            exec.init_parameter(&zeros, &mut store)?;
+            exec.init_contants(&mut store);
+        }
+        return Ok(ResidualSystem {
+            execs,
+            names,
+            indices,
+            store,
+        });
+    }
+    // parameter names in jacobian column order
+    pub fn names(&self) -> &[String] {
+        return &self.names;
+    }
+    pub fn len(&self) -> usize {
+        return self.execs.len();
+    }
+    pub fn is_empty(&self) -> bool {
+        return self.execs.is_empty();
+    }
+    pub fn evaluate(&mut self, x: &[f64]) -> Result<(Vec<f64>, Matrix)> {
+        for (index, value) in self.indices.iter().zip(x.iter()) {
+            self.get(*index)?.borrow_mut().value = *value as f32;
+        }
+        let mut residuals = Vec::with_capacity(self.execs.len());
+        let mut jacobian = Matrix::new(self.execs.len(), self.indices.len());
+        for row in 0..self.execs.len() {
+            for index in self.indices.iter() {
+                self.get(*index)?.borrow_mut().grad = 0.0;
+            }
+            let exec = &mut self.execs[row];
+            exec.forward(&mut self.store)?;
+            exec.backward(&mut self.store)?;
+            residuals.push(exec.get_output_value(&mut self.store)?.value as f64);
+            for (col, index) in self.indices.iter().enumerate() {
+                jacobian[(row, col)] = self.get(*index)?.borrow().grad as f64;
+            }
+        }
+        return Ok((residuals, jacobian));
+    }
+    fn get(&self, index: usize) -> Result<&RefCell<Float>> {
+        return self
+            .store
+            .get(&index)
+            .ok_or(FFError::Store(format!("not find id: {}", index)));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LeastSquaresResult {
+    pub parameters: HashMap<String, f64>,
+    pub residuals: Vec<f64>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+pub struct LeastSquares {
+    method: Method,
+    max_iterations: usize,
+    tolerance: f64,
+    step_tolerance: f64,
+    lambda: f64,
+    lambda_factor: f64,
+    bounds: HashMap<String, Bound>,
+}
+impl LeastSquares {
+    pub fn new(method: Method) -> Self {
+        LeastSquares {
+            method,
+            max_iterations: 100,
+            tolerance: 1e-6,
+            step_tolerance: 1e-10,
+            lambda: 1e-3,
+            lambda_factor: 10.0,
+            bounds: HashMap::new(),
+        }
+    }
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        return self;
+    }
+    // stop once the residual norm is below `tolerance` or a step is shorter than `step_tolerance`
+    pub fn with_tolerance(mut self, tolerance: f64, step_tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self.step_tolerance = step_tolerance;
+        return self;
+    }
+    // initial Levenberg-Marquardt damping and the factor it is scaled by after each trial
+    pub fn with_damping(mut self, lambda: f64, lambda_factor: f64) -> Self {
+        self.lambda = lambda;
+        self.lambda_factor = lambda_factor;
+        return self;
+    }
+    // every iterate is projected onto the bounds, whatever their `BoundMode`
+    pub fn with_bounds(mut self, bounds: HashMap<String, Bound>) -> Self {
+        self.bounds = bounds;
+        return self;
+    }
+    pub fn solve(
+        &self,
+        polys: &[Polynomial],
+        init: &HashMap<String, f32>,
+    ) -> Result<LeastSquaresResult> {
+        for bound in self.bounds.values() {
+            bound.validate()?;
+        }
+        let mut system = ResidualSystem::new(polys)?;
+        let mut x = system
+            .names()
+            .iter()
+            .map(|name| {
+                init.get(name)
+                    .map(|v| *v as f64)
+                    .ok_or(FFError::Store(format!("not find key: {}", name)))
+            })
+            .collect::<Result<Vec<f64>>>()?;
+        let bounds = system
+            .names()
+            .iter()
+            .map(|name| self.bounds.get(name).copied())
+            .collect::<Vec<Option<Bound>>>();
+        project(&bounds, &mut x);
+        if self.method == Method::Newton && system.len() != x.len() {
+            return Err(FFError::Linalg(format!(
+                "newton needs a square system, got {} equations in {} unknowns",
+                system.len(),
+                x.len()
+            )));
+        }
+        let (mut r, mut j) = system.evaluate(&x)?;
+        let mut lambda = self.lambda;
+        let mut iterations = 0;
+        while iterations < self.max_iterations && norm(&r) > self.tolerance {
+            iterations += 1;
+            let minus_r = r.iter().map(|v| -v).collect::<Vec<f64>>();
+            let dx = match self.method {
+                Method::Newton => linalg::lu_solve(&j, &minus_r)?,
+                Method::GaussNewton => gauss_newton_step(&j, &minus_r)?,
+                Method::LevenbergMarquardt => {
+                    let step = self.levenberg_marquardt_step(
+                        &mut system,
+                        &bounds,
+                        &x,
+                        &r,
+                        &j,
+                        &mut lambda,
+                    )?;
+                    match step {
+                        Some(dx) => dx,
+                        None => break,
+                    }
+                }
+            };
+            let next = step_to(&bounds, &x, &dx);
+            let dx = next
+                .iter()
+                .zip(x.iter())
+                .map(|(n, o)| n - o)
+                .collect::<Vec<f64>>();
+            x = next;
+            (r, j) = system.evaluate(&x)?;
+            if norm(&dx) <= self.step_tolerance * (norm(&x) + self.step_tolerance) {
+                break;
+            }
+        }
+        return Ok(LeastSquaresResult {
+            parameters: system.names().iter().cloned().zip(x).collect(),
+            converged: norm(&r) <= self.tolerance,
+            residuals: r,
+            iterations,
+        });
+    }
+    // returns the first damped step that decreases the cost, or `None` once lambda blows up
+    fn levenberg_marquardt_step(
+        &self,
+        system: &mut ResidualSystem,
+        bounds: &[Option<Bound>],
+        x: &[f64],
+        r: &[f64],
+        j: &Matrix,
+        lambda: &mut f64,
+    ) -> Result<Option<Vec<f64>>> {
+        let jt = j.transpose();
+        let jtj = jt.mul(j);
+        let minus_g = jt.mul_vec(r).iter().map(|v| -v).collect::<Vec<f64>>();
+        let cost = norm(r);
+        while *lambda < 1e16 {
+            let mut a = jtj.clone();
+            for i in 0..a.rows() {
+                a[(i, i)] += *lambda * jtj[(i, i)].max(1e-12);
+            }
+            let dx =
+                linalg::cholesky_solve(&a, &minus_g).or_else(|_| linalg::lu_solve(&a, &minus_g));
+            if let Ok(dx) = dx {
+                let trial = step_to(bounds, x, &dx);
+                let (trial_r, _) = system.evaluate(&trial)?;
+                if norm(&trial_r) < cost {
+                    *lambda = (*lambda / self.lambda_factor).max(1e-12);
+                    return Ok(Some(dx));
+                }
+            }
+            *lambda *= self.lambda_factor;
+        }
+        return Ok(None);
+    }
+}
+
+// overdetermined or square systems use QR, underdetermined ones the minimum-norm step
+fn gauss_newton_step(j: &Matrix, minus_r: &[f64]) -> Result<Vec<f64>> {
+    if j.rows() >= j.cols() {
+        return linalg::qr_least_squares(j, minus_r);
+    }
+    let jt = j.transpose();
+    let y = linalg::cholesky_solve(&j.mul(&jt), minus_r)?;
+    return Ok(jt.mul_vec(&y));
+}
+
+fn project(bounds: &[Option<Bound>], x: &mut [f64]) {
+    for (bound, x) in bounds.iter().zip(x.iter_mut()) {
+        if let Some(b) = bound {
+            *x = x.max(b.lower as f64).min(b.upper as f64);
+        }
+    }
+}
+// x + dx projected onto the bounds
+fn step_to(bounds: &[Option<Bound>], x: &[f64], dx: &[f64]) -> Vec<f64> {
+    let mut next = x
+        .iter()
+        .zip(dx.iter())
+        .map(|(x, d)| x + d)
+        .collect::<Vec<f64>>();
+    project(bounds, &mut next);
+    return next;
+}
+
+fn norm(v: &[f64]) -> f64 {
+    return v.iter().map(|x| x * x).sum::<f64>().sqrt();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::polynomial::Term;
+    fn term(coefficient: f32, variables: &[(&str, usize)]) -> Term {
+        let mut t = Term::new();
+        t.set_coefficient(coefficient);
+        variables
+            .iter()
+            .for_each(|(name, order)| t.add_variable(name, *order));
+        return t;
+    }
+    // x^2 + y^2 = 5, x y = 2
+    fn circle_and_hyperbola() -> Vec<Polynomial> {
+        let mut p0 = Polynomial::new();
+        p0.add_term(term(1.0, &[("x", 2)]));
+        p0.add_term(term(1.0, &[("y", 2)]));
+        p0.add_term(term(-5.0, &[]));
+        let mut p1 = Polynomial::new();
+        p1.add_term(term(1.0, &[("x", 1), ("y", 1)]));
+        p1.add_term(term(-2.0, &[]));
+        return vec![p0, p1];
+    }
+    #[test]
+    fn bounded() {
+        // x starts outside of the box and is projected back to 0
+        let init = HashMap::from([("x".to_string(), -3.0), ("y".to_string(), 0.5)]);
+        let bounds = HashMap::from([
+            ("x".to_string(), Bound::new(0.0, f32::INFINITY)),
+            ("y".to_string(), Bound::new(0.0, f32::INFINITY)),
+        ]);
+        let res = LeastSquares::new(Method::LevenbergMarquardt)
+            .with_bounds(bounds)
+            .solve(&circle_and_hyperbola(), &init)
+            .unwrap();
+        assert!(res.converged, "{:?}", res);
+        assert!(
+            (res.parameters["x"] - 2.0).abs() < 1e-5 || (res.parameters["x"] - 1.0).abs() < 1e-5
+        );
+        assert!(res.parameters["y"] > 0.0);
+    }
+    #[test]
+    fn jacobian() {
+        let mut system = ResidualSystem::new(&circle_and_hyperbola()).unwrap();
+        assert_eq!(system.names(), ["x", "y"]);
+        let (r, j) = system.evaluate(&[1.0, 3.0]).unwrap();
+        assert_eq!(r, vec![5.0, 1.0]);
+        assert_eq!(j, Matrix::from_rows(&[vec![2.0, 6.0], vec![3.0, 1.0]]));
+    }
+    #[test]
+    fn methods() {
+        let init = HashMap::from([("x".to_string(), 3.0), ("y".to_string(), 0.5)]);
+        for method in [
+            Method::Newton,
+            Method::GaussNewton,
+            Method::LevenbergMarquardt,
+        ] {
+            let res = LeastSquares::new(method)
+                .solve(&circle_and_hyperbola(), &init)
+                .unwrap();
+            assert!(res.converged, "{:?} did not converge: {:?}", method, res);
+            assert!((res.parameters["x"] - 2.0).abs() < 1e-5);
+            assert!((res.parameters["y"] - 1.0).abs() < 1e-5);
+        }
+    }
+}
@@ -1,12 +1,38 @@
+// explicit returns are the house style
+#![allow(clippy::needless_return)]
+pub mod bigint;
+pub mod bounds;
+pub mod coefficient;
+pub mod complex;
+pub mod constraint;
+pub mod deflation;
+pub mod division;
 pub mod error;
 pub mod executor;
 pub mod float;
 pub mod graph;
+pub mod groebner;
+pub mod homotopy;
+pub mod lbfgs;
+pub mod least_squares;
+pub mod linalg;
+pub mod loss;
+pub mod multistart;
 pub mod op;
 pub mod ops;
 pub mod optimizer;
 pub mod polynomial;
+pub mod random;
+pub mod rational;
+pub mod resultant;
+pub mod roots;
+pub mod scaling;
+pub mod scheduler;
+pub mod solver;
 pub mod store;
+pub mod sturm;
+#[cfg(test)]
+mod test_helpers;
 // enum Op{
 //     Add(usize,usize,usize),
 //     Minus(usize,usize),
@@ -0,0 +1,536 @@
+use crate::complex::Complex;
+use crate::error::{FFError, Result};
+use std::ops::{Index, IndexMut};
+
+// pivots below this (relative to the largest entry) are treated as zero
+const SINGULAR_TOLERANCE: f64 = 1e-12;
+// QR sweeps allowed per eigenvalue before giving up
+const MAX_QR_ITERATIONS: usize = 60;
+
+// dense row-major f64 matrix
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    rows: usize,
+    cols: usize,
+    data: Vec<f64>,
+}
+impl Matrix {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        Matrix {
+            rows,
+            cols,
+            data: vec![0.0; rows * cols],
+        }
+    }
+    pub fn identity(n: usize) -> Self {
+        let mut m = Matrix::new(n, n);
+        (0..n).for_each(|i| m[(i, i)] = 1.0);
+        return m;
+    }
+    pub fn from_rows(rows: &[Vec<f64>]) -> Self {
+        let cols = rows.first().map_or(0, |r| r.len());
+        let mut m = Matrix::new(rows.len(), cols);
+        for (i, row) in rows.iter().enumerate() {
+            m.data[i * cols..(i + 1) * cols].copy_from_slice(row);
+        }
+        return m;
+    }
+    pub fn rows(&self) -> usize {
+        return self.rows;
+    }
+    pub fn cols(&self) -> usize {
+        return self.cols;
+    }
+    pub fn row(&self, i: usize) -> &[f64] {
+        return &self.data[i * self.cols..(i + 1) * self.cols];
+    }
+    pub fn transpose(&self) -> Matrix {
+        let mut t = Matrix::new(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                t[(j, i)] = self[(i, j)];
+            }
+        }
+        return t;
+    }
+    pub fn mul(&self, other: &Matrix) -> Matrix {
+        assert_eq!(self.cols, other.rows, "matrix dimensions do not match");
+        let mut res = Matrix::new(self.rows, other.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = self[(i, k)];
+                if a == 0.0 {
+                    continue;
+                }
+                for j in 0..other.cols {
+                    res[(i, j)] += a * other[(k, j)];
+                }
+            }
+        }
+        return res;
+    }
+    pub fn mul_vec(&self, v: &[f64]) -> Vec<f64> {
+        assert_eq!(self.cols, v.len(), "matrix dimensions do not match");
+        return (0..self.rows)
+            .map(|i| self.row(i).iter().zip(v.iter()).map(|(a, b)| a * b).sum())
+            .collect();
+    }
+    fn max_abs(&self) -> f64 {
+        return self.data.iter().fold(0.0, |m, v| m.max(v.abs()));
+    }
+}
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+    fn index(&self, (i, j): (usize, usize)) -> &f64 {
+        return &self.data[i * self.cols + j];
+    }
+}
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut f64 {
+        return &mut self.data[i * self.cols + j];
+    }
+}
+
+// solves a x = b by LU decomposition with partial pivoting
+pub fn lu_solve(a: &Matrix, b: &[f64]) -> Result<Vec<f64>> {
+    let n = a.rows;
+    if a.cols != n || b.len() != n {
+        return Err(FFError::Linalg(format!(
+            "lu_solve needs a square system, got {}x{} with {} right-hand sides",
+            a.rows,
+            a.cols,
+            b.len()
+        )));
+    }
+    let mut lu = a.clone();
+    let mut x = b.to_vec();
+    let tolerance = SINGULAR_TOLERANCE * a.max_abs().max(1.0);
+    for k in 0..n {
+        let pivot = (k..n)
+            .max_by(|i, j| lu[(*i, k)].abs().total_cmp(&lu[(*j, k)].abs()))
+            .unwrap();
+        if lu[(pivot, k)].abs() <= tolerance {
+            return Err(FFError::Linalg(format!("singular matrix at column {}", k)));
+        }
+        if pivot != k {
+            for j in 0..n {
+                lu.data.swap(k * n + j, pivot * n + j);
+            }
+            x.swap(k, pivot);
+        }
+        for i in k + 1..n {
+            let factor = lu[(i, k)] / lu[(k, k)];
+            if factor == 0.0 {
+                continue;
+            }
+            for j in k..n {
+                lu[(i, j)] -= factor * lu[(k, j)];
+            }
+            x[i] -= factor * x[k];
+        }
+    }
+    back_substitute(&lu, &mut x);
+    return Ok(x);
+}
+
+// solves a x = b for symmetric positive definite a
+pub fn cholesky_solve(a: &Matrix, b: &[f64]) -> Result<Vec<f64>> {
+    let n = a.rows;
+    if a.cols != n || b.len() != n {
+        return Err(FFError::Linalg(format!(
+            "cholesky_solve needs a square system, got {}x{} with {} right-hand sides",
+            a.rows,
+            a.cols,
+            b.len()
+        )));
+    }
+    let mut l = Matrix::new(n, n);
+    for i in 0..n {
+        for j in 0..=i {
+            let sum = (0..j).map(|k| l[(i, k)] * l[(j, k)]).sum::<f64>();
+            if i == j {
+                let d = a[(i, i)] - sum;
+                if d <= 0.0 {
+                    return Err(FFError::Linalg(format!(
+                        "matrix is not positive definite at row {}",
+                        i
+                    )));
+                }
+                l[(i, i)] = d.sqrt();
+            } else {
+                l[(i, j)] = (a[(i, j)] - sum) / l[(j, j)];
+            }
+        }
+    }
+    let mut y = b.to_vec();
+    for i in 0..n {
+        let sum = (0..i).map(|k| l[(i, k)] * y[k]).sum::<f64>();
+        y[i] = (y[i] - sum) / l[(i, i)];
+    }
+    back_substitute(&l.transpose(), &mut y);
+    return Ok(y);
+}
+
+// minimizes |a x - b| by Householder QR, a must have full column rank
+pub fn qr_least_squares(a: &Matrix, b: &[f64]) -> Result<Vec<f64>> {
+    let (m, n) = (a.rows, a.cols);
+    if m < n || b.len() != m {
+        return Err(FFError::Linalg(format!(
+            "qr_least_squares needs rows >= cols, got {}x{} with {} right-hand sides",
+            m,
+            n,
+            b.len()
+        )));
+    }
+    let mut r = a.clone();
+    let mut y = b.to_vec();
+    let tolerance = SINGULAR_TOLERANCE * a.max_abs().max(1.0);
+    for k in 0..n {
+        let norm = (k..m).map(|i| r[(i, k)] * r[(i, k)]).sum::<f64>().sqrt();
+        if norm <= tolerance {
+            return Err(FFError::Linalg(format!("rank deficient at column {}", k)));
+        }
+        let alpha = if r[(k, k)] > 0.0 { -norm } else { norm };
+        let mut v = (k..m).map(|i| r[(i, k)]).collect::<Vec<f64>>();
+        v[0] -= alpha;
+        let vv = v.iter().map(|x| x * x).sum::<f64>();
+        for j in k..n {
+            let s = 2.0 * (k..m).map(|i| v[i - k] * r[(i, j)]).sum::<f64>() / vv;
+            (k..m).for_each(|i| r[(i, j)] -= s * v[i - k]);
+        }
+        let s = 2.0 * (k..m).map(|i| v[i - k] * y[i]).sum::<f64>() / vv;
+        (k..m).for_each(|i| y[i] -= s * v[i - k]);
+    }
+    let mut upper = Matrix::new(n, n);
+    for i in 0..n {
+        for j in i..n {
+            upper[(i, j)] = r[(i, j)];
+        }
+    }
+    y.truncate(n);
+    back_substitute(&upper, &mut y);
+    return Ok(y);
+}
+
+// solves u x = y in place for upper triangular u
+fn back_substitute(u: &Matrix, y: &mut [f64]) {
+    let n = u.rows;
+    for i in (0..n).rev() {
+        let sum = (i + 1..n).map(|j| u[(i, j)] * y[j]).sum::<f64>();
+        y[i] = (y[i] - sum) / u[(i, i)];
+    }
+}
+
+// all eigenvalues of a square matrix: balancing, reduction to upper Hessenberg form
+// and Francis double-shift QR, complex pairs come out conjugate
+pub fn eigenvalues(a: &Matrix) -> Result<Vec<Complex>> {
+    if a.cols != a.rows {
+        return Err(FFError::Linalg(format!(
+            "eigenvalues needs a square matrix, got {}x{}",
+            a.rows, a.cols
+        )));
+    }
+    let mut h = a.clone();
+    balance(&mut h);
+    hessenberg(&mut h);
+    return hessenberg_qr(&mut h);
+}
+
+// similarity scaling by powers of two so that rows and columns have comparable norms
+fn balance(a: &mut Matrix) {
+    const RADIX: f64 = 2.0;
+    let n = a.rows;
+    let mut done = false;
+    while !done {
+        done = true;
+        for i in 0..n {
+            let c0 = (0..n)
+                .filter(|j| *j != i)
+                .map(|j| a[(j, i)].abs())
+                .sum::<f64>();
+            let r = (0..n)
+                .filter(|j| *j != i)
+                .map(|j| a[(i, j)].abs())
+                .sum::<f64>();
+            if c0 == 0.0 || r == 0.0 {
+                continue;
+            }
+            let (mut c, mut f) = (c0, 1.0);
+            while c < r / RADIX {
+                f *= RADIX;
+                c *= RADIX * RADIX;
+            }
+            while c > r * RADIX {
+                f /= RADIX;
+                c /= RADIX * RADIX;
+            }
+            if (c + r) / f < 0.95 * (c0 + r) {
+                done = false;
+                for j in 0..n {
+                    a[(i, j)] /= f;
+                    a[(j, i)] *= f;
+                }
+            }
+        }
+    }
+}
+
+// reduces to upper Hessenberg form by stabilized elementary similarity transformations
+fn hessenberg(a: &mut Matrix) {
+    let n = a.rows;
+    for m in 1..n.saturating_sub(1) {
+        let pivot = (m..n)
+            .max_by(|i, j| a[(*i, m - 1)].abs().total_cmp(&a[(*j, m - 1)].abs()))
+            .unwrap();
+        let x = a[(pivot, m - 1)];
+        if pivot != m {
+            for j in m - 1..n {
+                a.data.swap(pivot * n + j, m * n + j);
+            }
+            for j in 0..n {
+                a.data.swap(j * n + pivot, j * n + m);
+            }
+        }
+        if x == 0.0 {
+            continue;
+        }
+        for i in m + 1..n {
+            let y = a[(i, m - 1)] / x;
+            if y == 0.0 {
+                continue;
+            }
+            a[(i, m - 1)] = 0.0;
+            for j in m..n {
+                a[(i, j)] -= y * a[(m, j)];
+            }
+            for j in 0..n {
+                a[(j, m)] += y * a[(j, i)];
+            }
+        }
+    }
+}
+
+// eigenvalues of an upper Hessenberg matrix, destroys it
+fn hessenberg_qr(a: &mut Matrix) -> Result<Vec<Complex>> {
+    let n = a.rows;
+    let mut w = vec![Complex::zero(); n];
+    let mut norm = 0.0;
+    for i in 0..n {
+        for j in i.saturating_sub(1)..n {
+            norm += a[(i, j)].abs();
+        }
+    }
+    // last row of the active block, the block shrinks as eigenvalues split off
+    let mut last = n as isize - 1;
+    // accumulated exceptional shifts
+    let mut t = 0.0;
+    while last >= 0 {
+        let mut iterations = 0;
+        loop {
+            let nn = last as usize;
+            // a negligible subdiagonal element splits the matrix at l
+            let mut l = nn;
+            while l >= 1 {
+                let mut s = a[(l - 1, l - 1)].abs() + a[(l, l)].abs();
+                if s == 0.0 {
+                    s = norm;
+                }
+                if a[(l, l - 1)].abs() <= f64::EPSILON * s {
+                    a[(l, l - 1)] = 0.0;
+                    break;
+                }
+                l -= 1;
+            }
+            let mut x = a[(nn, nn)];
+            if l == nn {
+                w[nn] = Complex::from_real(x + t);
+                last -= 1;
+            } else if l == nn - 1 {
+                // trailing 2x2 block, a real pair or a conjugate pair
+                let y = a[(nn - 1, nn - 1)];
+                let ww = a[(nn, nn - 1)] * a[(nn - 1, nn)];
+                let p = 0.5 * (y - x);
+                let q = p * p + ww;
+                let z = q.abs().sqrt();
+                x += t;
+                if q >= 0.0 {
+                    let z = p + z.copysign(p);
+                    w[nn - 1] = Complex::from_real(x + z);
+                    w[nn] = Complex::from_real(if z != 0.0 { x - ww / z } else { x + z });
+                } else {
+                    w[nn - 1] = Complex::new(x + p, -z);
+                    w[nn] = Complex::new(x + p, z);
+                }
+                last -= 2;
+            } else {
+                if iterations == MAX_QR_ITERATIONS {
+                    return Err(FFError::Linalg(format!(
+                        "QR iteration did not converge for eigenvalue {}",
+                        nn
+                    )));
+                }
+                let mut y = a[(nn - 1, nn - 1)];
+                let mut ww = a[(nn, nn - 1)] * a[(nn - 1, nn)];
+                if iterations == 10 || iterations == 20 {
+                    // exceptional shift to break cycles
+                    t += x;
+                    for i in 0..=nn {
+                        a[(i, i)] -= x;
+                    }
+                    let s = a[(nn, nn - 1)].abs() + a[(nn - 1, nn - 2)].abs();
+                    x = 0.75 * s;
+                    y = x;
+                    ww = -0.4375 * s * s;
+                }
+                iterations += 1;
+                double_shift_step(a, l, nn, x, y, ww);
+            }
+            if last < 0 || l as isize >= last - 1 {
+                break;
+            }
+        }
+    }
+    return Ok(w);
+}
+
+// one Francis double-shift QR sweep on the active block l..=nn, the shifts are the
+// eigenvalues of the 2x2 block [[y, .], [., x]] with off-diagonal product ww
+fn double_shift_step(a: &mut Matrix, l: usize, nn: usize, x: f64, y: f64, ww: f64) {
+    // start at two consecutive small subdiagonal elements if there are any
+    let mut m = nn - 2;
+    let (mut p, mut q, mut r);
+    loop {
+        let z = a[(m, m)];
+        let (rr, ss) = (x - z, y - z);
+        p = (rr * ss - ww) / a[(m + 1, m)] + a[(m, m + 1)];
+        q = a[(m + 1, m + 1)] - z - rr - ss;
+        r = a[(m + 2, m + 1)];
+        let s = p.abs() + q.abs() + r.abs();
+        p /= s;
+        q /= s;
+        r /= s;
+        if m == l {
+            break;
+        }
+        let u = a[(m, m - 1)].abs() * (q.abs() + r.abs());
+        let v = p.abs() * (a[(m - 1, m - 1)].abs() + z.abs() + a[(m + 1, m + 1)].abs());
+        if u <= f64::EPSILON * v {
+            break;
+        }
+        m -= 1;
+    }
+    for i in m + 2..=nn {
+        a[(i, i - 2)] = 0.0;
+        if i != m + 2 {
+            a[(i, i - 3)] = 0.0;
+        }
+    }
+    let mut scale = 0.0;
+    for k in m..nn {
+        if k != m {
+            p = a[(k, k - 1)];
+            q = a[(k + 1, k - 1)];
+            r = if k != nn - 1 { a[(k + 2, k - 1)] } else { 0.0 };
+            scale = p.abs() + q.abs() + r.abs();
+            if scale != 0.0 {
+                p /= scale;
+                q /= scale;
+                r /= scale;
+            }
+        }
+        let s = (p * p + q * q + r * r).sqrt().copysign(p);
+        if s == 0.0 {
+            continue;
+        }
+        if k == m {
+            if l != m {
+                a[(k, k - 1)] = -a[(k, k - 1)];
+            }
+        } else {
+            a[(k, k - 1)] = -s * scale;
+        }
+        // Householder reflector I - v v^T / h applied from both sides
+        p += s;
+        let (hx, hy, hz) = (p / s, q / s, r / s);
+        q /= p;
+        r /= p;
+        for j in k..=nn {
+            let mut v = a[(k, j)] + q * a[(k + 1, j)];
+            if k != nn - 1 {
+                v += r * a[(k + 2, j)];
+                a[(k + 2, j)] -= v * hz;
+            }
+            a[(k + 1, j)] -= v * hy;
+            a[(k, j)] -= v * hx;
+        }
+        for i in l..=nn.min(k + 3) {
+            let mut v = hx * a[(i, k)] + hy * a[(i, k + 1)];
+            if k != nn - 1 {
+                v += hz * a[(i, k + 2)];
+                a[(i, k + 2)] -= v * r;
+            }
+            a[(i, k + 1)] -= v * q;
+            a[(i, k)] -= v;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn assert_close(a: &[f64], b: &[f64]) {
+        assert!(a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-9));
+    }
+    #[test]
+    fn lu() {
+        let a = Matrix::from_rows(&[
+            vec![0.0, 2.0, 1.0],
+            vec![1.0, -2.0, -3.0],
+            vec![-1.0, 1.0, 2.0],
+        ]);
+        let x = lu_solve(&a, &[-8.0, 0.0, 3.0]).unwrap();
+        assert_close(&a.mul_vec(&x), &[-8.0, 0.0, 3.0]);
+        let singular = Matrix::from_rows(&[vec![1.0, 2.0], vec![2.0, 4.0]]);
+        assert!(lu_solve(&singular, &[1.0, 2.0]).is_err());
+    }
+    #[test]
+    fn cholesky() {
+        let a = Matrix::from_rows(&[vec![4.0, 2.0], vec![2.0, 3.0]]);
+        let x = cholesky_solve(&a, &[2.0, 1.0]).unwrap();
+        assert_close(&x, &[0.5, 0.0]);
+    }
+    #[test]
+    fn least_squares() {
+        // fit y = c0 + c1 t through (0, 1), (1, 3), (2, 5), (3, 7)
+        let a = Matrix::from_rows(&[
+            vec![1.0, 0.0],
+            vec![1.0, 1.0],
+            vec![1.0, 2.0],
+            vec![1.0, 3.0],
+        ]);
+        let x = qr_least_squares(&a, &[1.0, 3.0, 5.0, 7.0]).unwrap();
+        assert_close(&x, &[1.0, 2.0]);
+    }
+    #[test]
+    fn eigen() {
+        let a = Matrix::from_rows(&[
+            vec![1.0, -3.0, 0.5, 2.0],
+            vec![2.0, 1.0, 0.0, -1.0],
+            vec![0.0, 0.0, 2.0, 4.0],
+            vec![0.0, 0.0, 1.0, 2.0],
+        ]);
+        // block triangular: 1 +- i sqrt(6) from the top block, 0 and 4 from the bottom one
+        let mut values = eigenvalues(&a).unwrap();
+        values.sort_by(|a, b| a.re.total_cmp(&b.re).then(a.im.total_cmp(&b.im)));
+        let expected = [
+            Complex::zero(),
+            Complex::new(1.0, -6f64.sqrt()),
+            Complex::new(1.0, 6f64.sqrt()),
+            Complex::from_real(4.0),
+        ];
+        for (v, e) in values.iter().zip(expected.iter()) {
+            assert!((*v - *e).norm() < 1e-9, "{} != {}", v, e);
+        }
+        assert!(eigenvalues(&Matrix::new(2, 3)).is_err());
+    }
+}
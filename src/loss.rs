@@ -0,0 +1,198 @@
+use crate::error::{FFError, Result};
+use crate::ops::ScalarFunction;
+use crate::polynomial::Polynomial;
+use std::collections::HashMap;
+
+// what a residual r contributes to the loss. every loss behaves like r^2 near 0, so
+// tolerances mean the same whichever is chosen, the robust ones grow slower for large
+// |r| and let outlying equations or data points pull less
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Loss {
+    // r^2
+    #[default]
+    Squared,
+    // |r|, not differentiable at 0 where its gradient is taken as 0
+    Absolute,
+    // r^2 for |r| <= delta, linear 2 delta |r| - delta^2 beyond
+    Huber(f32),
+    // 2 c^2 log(cosh(r / c)), a smooth huber
+    LogCosh(f32),
+    // c^2 log(1 + (r / c)^2), grows only logarithmically
+    Cauchy(f32),
+}
+impl Loss {
+    // "squared", "absolute", "huber", "logcosh" or "cauchy", the scale of the last three
+    // follows a colon and defaults to 1, e.g. "huber:0.5"
+    pub fn parse(string: &str) -> Result<Self> {
+        let (name, scale) = match string.split_once(':') {
+            Some((name, scale)) => (name, scale.parse::<f32>()?),
+            None => (string, 1.0),
+        };
+        if !(scale.is_finite() && scale > 0.0) {
+            return Err(FFError::Parse(format!(
+                "loss scale must be positive, got {}",
+                scale
+            )));
+        }
+        return match name {
+            "squared" => Ok(Loss::Squared),
+            "absolute" => Ok(Loss::Absolute),
+            "huber" => Ok(Loss::Huber(scale)),
+            "logcosh" => Ok(Loss::LogCosh(scale)),
+            "cauchy" => Ok(Loss::Cauchy(scale)),
+            other => Err(FFError::Parse(format!("unknown loss: {}", other))),
+        };
+    }
+}
+impl ScalarFunction for Loss {
+    fn value(&self, r: f32) -> f32 {
+        return match *self {
+            Loss::Squared => r * r,
+            Loss::Absolute => r.abs(),
+            Loss::Huber(delta) => {
+                if r.abs() <= delta {
+                    r * r
+                } else {
+                    2.0 * delta * r.abs() - delta * delta
+                }
+            }
+            Loss::LogCosh(c) => {
+                // log(cosh(x)) = |x| + log(1 + e^(-2|x|)) - log(2), no overflow for large x
+                let x = (r / c).abs();
+                2.0 * c * c * (x + (-2.0 * x).exp().ln_1p() - std::f32::consts::LN_2)
+            }
+            Loss::Cauchy(c) => c * c * (r / c).powi(2).ln_1p(),
+        };
+    }
+    // d value / d r
+    fn derivative(&self, r: f32) -> f32 {
+        return match *self {
+            Loss::Squared => 2.0 * r,
+            Loss::Absolute => sign(r),
+            Loss::Huber(delta) => {
+                if r.abs() <= delta {
+                    2.0 * r
+                } else {
+                    2.0 * delta * sign(r)
+                }
+            }
+            Loss::LogCosh(c) => 2.0 * c * (r / c).tanh(),
+            Loss::Cauchy(c) => 2.0 * r / (1.0 + (r / c).powi(2)),
+        };
+    }
+}
+fn sign(r: f32) -> f32 {
+    return if r > 0.0 {
+        1.0
+    } else if r < 0.0 {
+        -1.0
+    } else {
+        0.0
+    };
+}
+
+// residuals model(x_k) - y_k of a data fit, one per sample (x_k, y_k). the data variables
+// of x_k are substituted, so the residuals are polynomials in the model parameters only
+// and go through the same graph builders and solvers as equations do
+pub fn data_residuals(
+    model: &Polynomial,
+    samples: &[(HashMap<String, f32>, f32)],
+) -> Vec<Polynomial> {
+    return samples
+        .iter()
+        .map(|(x, y)| {
+            let values = x
+                .iter()
+                .map(|(name, value)| (name.clone(), Polynomial::constant(*value)))
+                .collect::<HashMap<String, Polynomial>>();
+            return &model.compose(&values) - &Polynomial::constant(*y);
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Executor;
+    use crate::graph::NodeAllocator;
+    use crate::lbfgs::LBFGS;
+    use crate::optimizer::Optimizer;
+    use crate::polynomial::tranform_weighted_system_to_graph;
+    use crate::solver::Solver;
+    use crate::store::Store;
+    #[test]
+    fn losses() {
+        let losses = [
+            Loss::Squared,
+            Loss::Absolute,
+            Loss::Huber(0.5),
+            Loss::LogCosh(0.5),
+            Loss::Cauchy(0.5),
+        ];
+        for loss in losses {
+            assert_eq!(loss.value(0.0), 0.0);
+            // central differences agree with the analytic derivative away from kinks
+            for r in [-3.0f32, -0.2, 0.3, 2.0] {
+                let h = 1e-3;
+                let numeric = (loss.value(r + h) - loss.value(r - h)) / (2.0 * h);
+                assert!(
+                    (numeric - loss.derivative(r)).abs() < 1e-2,
+                    "{:?} at {}",
+                    loss,
+                    r
+                );
+            }
+        }
+        // like r^2 near 0, slower than it far away
+        for loss in [Loss::Huber(1.0), Loss::LogCosh(1.0), Loss::Cauchy(1.0)] {
+            assert!((loss.value(1e-2) - 1e-4).abs() < 1e-6);
+            assert!(loss.value(10.0) < 30.0);
+        }
+        assert!(Loss::LogCosh(1.0).value(1e3).is_finite());
+        assert_eq!(Loss::parse("huber:0.5").unwrap(), Loss::Huber(0.5));
+        assert_eq!(Loss::parse("cauchy").unwrap(), Loss::Cauchy(1.0));
+        assert!(Loss::parse("huber:-1").is_err());
+        assert!(Loss::parse("hinge").is_err());
+    }
+    #[test]
+    fn robust_fit() {
+        // y = 2t + 1 with one gross outlier at t = 4
+        let model = Polynomial::parse("a t + b").unwrap();
+        let samples = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0]
+            .iter()
+            .map(|t| {
+                let y = if *t == 4.0 { 40.0 } else { 2.0 * t + 1.0 };
+                (HashMap::from([("t".to_string(), *t)]), y)
+            })
+            .collect::<Vec<(HashMap<String, f32>, f32)>>();
+        let residuals = data_residuals(&model, &samples);
+        assert_eq!(residuals[1], Polynomial::parse("a + b - 3").unwrap());
+        let fit = |loss: Loss| {
+            let mut allocator = NodeAllocator::new();
+            let mut name_record = HashMap::new();
+            let factors = vec![1.0; residuals.len()];
+            let graph = tranform_weighted_system_to_graph(
+                &residuals,
+                &factors,
+                loss,
+                &mut name_record,
+                &mut allocator,
+            )
+            .unwrap();
+            let mut exec = Executor::new(graph, Optimizer::LBFGS(LBFGS::new(8)));
+            let mut s = Store::new();
+            let init = HashMap::from([("a".to_string(), 0.0), ("b".to_string(), 0.0)]);
+            exec.init_parameter(&init, &mut s).unwrap();
+            exec.init_contants(&mut s);
+            let report = Solver::new()
+                .with_max_iterations(500)
+                .with_parameter_tolerance(1e-7)
+                .solve(&mut exec, &mut s, &residuals)
+                .unwrap();
+            return report.parameters["a"];
+        };
+        // least squares is dragged off by the outlier, cauchy is not
+        assert!((fit(Loss::Squared) - 2.0).abs() > 1.0);
+        assert!((fit(Loss::Cauchy(1.0)) - 2.0).abs() < 0.05);
+    }
+}
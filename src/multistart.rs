@@ -0,0 +1,215 @@
+use crate::error::{FFError, Result};
+use crate::least_squares::{LeastSquares, Method};
+use crate::polynomial::Polynomial;
+use crate::random::{sample, Rng, Sampling};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct MultiStartSolution {
+    pub parameters: HashMap<String, f64>,
+    pub residuals: Vec<f64>,
+    // euclidean norm of the residuals
+    pub residual: f64,
+    // starts that converged to this solution
+    pub hits: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MultiStartResult {
+    // distinct converged solutions, sorted by their parameter vectors
+    pub solutions: Vec<MultiStartSolution>,
+    // every start is counted in exactly one of converged, not_converged and failed
+    pub starts: usize,
+    pub converged: usize,
+    // starts whose solve ended without meeting its tolerances
+    pub not_converged: usize,
+    // starts whose solve returned an error, e.g. a singular newton step
+    pub failed: usize,
+}
+
+// runs a local solver from many initial points spread over a box and keeps the
+// distinct roots it converges to
+pub struct MultiStart {
+    sampling: Sampling,
+    starts: usize,
+    seed: u64,
+    cluster_tolerance: f64,
+    solver: LeastSquares,
+}
+impl MultiStart {
+    pub fn new() -> Self {
+        MultiStart {
+            sampling: Sampling::Sobol,
+            starts: 32,
+            seed: 0,
+            cluster_tolerance: 1e-4,
+            solver: LeastSquares::new(Method::LevenbergMarquardt),
+        }
+    }
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        return self;
+    }
+    pub fn with_starts(mut self, starts: usize) -> Self {
+        self.starts = starts;
+        return self;
+    }
+    // the same seed gives the same initial points
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        return self;
+    }
+    // converged points closer than this (relative to their size) are one solution
+    pub fn with_cluster_tolerance(mut self, cluster_tolerance: f64) -> Self {
+        self.cluster_tolerance = cluster_tolerance;
+        return self;
+    }
+    pub fn with_solver(mut self, solver: LeastSquares) -> Self {
+        self.solver = solver;
+        return self;
+    }
+    // `region` gives the (lower, upper) sampling range of every variable
+    pub fn solve(
+        &self,
+        polys: &[Polynomial],
+        region: &HashMap<String, (f64, f64)>,
+    ) -> Result<MultiStartResult> {
+        let mut names = polys
+            .iter()
+            .flat_map(|p| p.variables())
+            .collect::<Vec<String>>();
+        names.sort();
+        names.dedup();
+        let ranges = names
+            .iter()
+            .map(|name| {
+                region.get(name).copied().ok_or(FFError::Polynomial(format!(
+                    "no sampling range for {}",
+                    name
+                )))
+            })
+            .collect::<Result<Vec<(f64, f64)>>>()?;
+        let mut rng = Rng::new(self.seed);
+        let points = sample(self.sampling, self.starts, names.len(), &mut rng)?;
+        let mut result = MultiStartResult {
+            solutions: Vec::new(),
+            starts: self.starts,
+            converged: 0,
+            not_converged: 0,
+            failed: 0,
+        };
+        let mut clusters = Vec::<(Vec<f64>, MultiStartSolution)>::new();
+        for point in points.iter() {
+            let init = names
+                .iter()
+                .zip(point.iter().zip(ranges.iter()))
+                .map(|(name, (u, (lo, hi)))| (name.clone(), (lo + (hi - lo) * u) as f32))
+                .collect::<HashMap<String, f32>>();
+            let Ok(local) = self.solver.solve(polys, &init) else {
+                result.failed += 1;
+                continue;
+            };
+            if !local.converged {
+                result.not_converged += 1;
+                continue;
+            }
+            result.converged += 1;
+            let x = names
+                .iter()
+                .map(|name| local.parameters[name])
+                .collect::<Vec<f64>>();
+            let residual = local.residuals.iter().map(|r| r * r).sum::<f64>().sqrt();
+            match clusters.iter_mut().find(|(y, _)| self.is_same(&x, y)) {
+                Some((y, solution)) => {
+                    solution.hits += 1;
+                    // the representative is the best-converged member
+                    if residual < solution.residual {
+                        *y = x;
+                        solution.parameters = local.parameters;
+                        solution.residuals = local.residuals;
+                        solution.residual = residual;
+                    }
+                }
+                None => clusters.push((
+                    x,
+                    MultiStartSolution {
+                        parameters: local.parameters,
+                        residuals: local.residuals,
+                        residual,
+                        hits: 1,
+                    },
+                )),
+            }
+        }
+        clusters.sort_by(|a, b| {
+            a.0.iter()
+                .zip(b.0.iter())
+                .map(|(x, y)| x.total_cmp(y))
+                .find(|o| o.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        result.solutions = clusters.into_iter().map(|(_, s)| s).collect();
+        return Ok(result);
+    }
+    fn is_same(&self, x: &[f64], y: &[f64]) -> bool {
+        let scale = x
+            .iter()
+            .chain(y.iter())
+            .fold(1.0, |m: f64, v| m.max(v.abs()));
+        return x
+            .iter()
+            .zip(y.iter())
+            .all(|(a, b)| (a - b).abs() <= self.cluster_tolerance * scale);
+    }
+}
+impl Default for MultiStart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn finds_every_root() {
+        // circle and hyperbola meet in four points
+        let polys = vec![
+            Polynomial::parse("x^2 + y^2 - 5").unwrap(),
+            Polynomial::parse("x y - 2").unwrap(),
+        ];
+        let region = HashMap::from([
+            ("x".to_string(), (-3.0, 3.0)),
+            ("y".to_string(), (-3.0, 3.0)),
+        ]);
+        for sampling in [Sampling::Uniform, Sampling::LatinHypercube, Sampling::Sobol] {
+            let result = MultiStart::new()
+                .with_sampling(sampling)
+                .with_starts(40)
+                .with_seed(3)
+                .solve(&polys, &region)
+                .unwrap();
+            let found = result
+                .solutions
+                .iter()
+                .map(|s| (s.parameters["x"].round(), s.parameters["y"].round()))
+                .collect::<Vec<(f64, f64)>>();
+            assert_eq!(
+                found,
+                vec![(-2.0, -1.0), (-1.0, -2.0), (1.0, 2.0), (2.0, 1.0)],
+                "{:?}",
+                sampling
+            );
+            assert!(result.solutions.iter().all(|s| s.residual < 1e-5));
+            let hits = result.solutions.iter().map(|s| s.hits).sum::<usize>();
+            assert_eq!(hits, result.converged);
+            assert_eq!(
+                result.converged + result.not_converged + result.failed,
+                result.starts
+            );
+        }
+        assert!(MultiStart::new()
+            .solve(&polys, &HashMap::from([("x".to_string(), (0.0, 1.0))]))
+            .is_err());
+    }
+}
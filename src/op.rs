@@ -1,6 +1,12 @@
-#[derive(Debug, Eq, PartialEq)]
+use crate::constraint::ConstraintMethod;
+use crate::loss::Loss;
+#[derive(Debug, PartialEq)]
 pub enum Op {
     Add(usize, usize, usize),
+    // phi(g), res
+    Constraint(ConstraintMethod, usize, usize),
+    // loss(r), res
+    Loss(Loss, usize, usize),
     Minus(usize, usize),
     Mul(usize, usize, usize),
 }
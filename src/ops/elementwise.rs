@@ -0,0 +1,85 @@
+use crate::error::{FFError, Result};
+use crate::float::Float;
+use crate::store::Store;
+use std::cell::RefCell;
+
+// a differentiable function of one node, e.g. a residual loss or a constraint penalty
+pub trait ScalarFunction {
+    fn value(&self, x: f32) -> f32;
+    // d value / d x
+    fn derivative(&self, x: f32) -> f32;
+}
+
+pub struct Elementwise<F: ScalarFunction>(F, usize, usize);
+impl<F: ScalarFunction> Elementwise<F> {
+    pub fn new(f: F, r: usize, res: usize) -> Self {
+        Elementwise(f, r, res)
+    }
+    pub fn forward(&self, s: &mut Store) -> Result<()> {
+        let mut res = Float::default();
+        {
+            let r = s
+                .get(&self.1)
+                .ok_or(FFError::Store(format!("not find id: {}", self.1)))?
+                .borrow();
+            if r.is_need_grad {
+                res.is_need_grad = true;
+            }
+            res.value = self.0.value(r.value);
+            res.grad = 0.0;
+        }
+        s.insert(self.2, RefCell::new(res));
+        return Ok(());
+    }
+    pub fn backward(&self, s: &mut Store) -> Result<()> {
+        let mut r = s
+            .get(&self.1)
+            .ok_or(FFError::Store(format!("not find id: {}", self.1)))?
+            .borrow_mut();
+        let res = s
+            .get(&self.2)
+            .ok_or(FFError::Store(format!("not find id: {}", self.2)))?
+            .borrow();
+        if r.is_need_grad {
+            r.grad += res.grad * self.0.derivative(r.value);
+        }
+        return Ok(());
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constraint::ConstraintMethod;
+    use crate::loss::Loss;
+    // (value, gradient of the input) for the input x and an output gradient of 1
+    fn run<F: ScalarFunction>(f: F, x: f32) -> (f32, f32) {
+        let mut s = Store::new();
+        {
+            let r = Float {
+                value: x,
+                is_need_grad: true,
+                ..Default::default()
+            };
+            s.insert(0, RefCell::new(r));
+        }
+        let op = Elementwise::new(f, 0, 1);
+        op.forward(&mut s).unwrap();
+        {
+            let mut res = s.get(&1).unwrap().borrow_mut();
+            assert!(res.is_need_grad);
+            res.grad = 1.0;
+        }
+        op.backward(&mut s).unwrap();
+        let r = s.get(&0).unwrap().borrow();
+        let res = s.get(&1).unwrap().borrow();
+        return (res.value, r.grad);
+    }
+    #[test]
+    fn loss_and_constraint() {
+        assert_eq!(run(Loss::Huber(1.0), 3.0), (5.0, 2.0));
+        assert_eq!(
+            run(ConstraintMethod::Barrier, -0.5),
+            (std::f32::consts::LN_2, 2.0)
+        );
+    }
+}
@@ -17,7 +17,7 @@ impl Minus {
             if r.is_need_grad {
                 res.is_need_grad = true;
             }
-            res.value = -1.0 * r.value;
+            res.value = -r.value;
             res.grad = 0.0;
         }
         s.insert(self.1, RefCell::new(res));
@@ -33,7 +33,7 @@ impl Minus {
             .ok_or(FFError::Store(format!("not find id: {}", self.1)))?
             .borrow();
         if r.is_need_grad {
-            r.grad += -1.0 * res.grad;
+            r.grad -= res.grad;
         }
         return Ok(());
     }
@@ -45,24 +45,26 @@ mod tests {
     fn minus() {
         let mut s = Store::new();
         {
-            let mut r = Float::default();
-            r.value = 1.0;
-            r.is_need_grad = true;
+            let r = Float {
+                value: 1.0,
+                is_need_grad: true,
+                ..Default::default()
+            };
             s.insert(0, RefCell::new(r));
         }
         let minus = Minus(0, 1);
-        minus.forward(&mut s);
+        minus.forward(&mut s).unwrap();
         {
             let mut res = s.get(&1).unwrap().borrow_mut();
             res.grad = 1.0;
         }
-        minus.backward(&mut s);
+        minus.backward(&mut s).unwrap();
         let r = s.get(&0).unwrap().borrow();
         let res = s.get(&1).unwrap().borrow();
         assert_eq!(res.value, -1.0);
-        assert_eq!(res.is_need_grad, true);
+        assert!(res.is_need_grad);
         assert_eq!(res.grad, 1.0);
-        assert_eq!(r.is_need_grad, true);
+        assert!(r.is_need_grad);
         assert_eq!(r.grad, -1.0);
     }
 }
@@ -1,6 +1,8 @@
 mod add;
+mod elementwise;
 mod minus;
 mod mul;
 pub use add::Add;
+pub use elementwise::{Elementwise, ScalarFunction};
 pub use minus::Minus;
 pub use mul::Mul;
@@ -69,32 +69,36 @@ mod tests {
     fn mul() {
         let mut s = Store::new();
         {
-            let mut r = Float::default();
-            r.value = 1.0;
-            r.is_need_grad = true;
+            let r = Float {
+                value: 1.0,
+                is_need_grad: true,
+                ..Default::default()
+            };
             s.insert(0, RefCell::new(r));
         }
         {
-            let mut l = Float::default();
-            l.value = 2.0;
-            l.is_need_grad = true;
+            let l = Float {
+                value: 2.0,
+                is_need_grad: true,
+                ..Default::default()
+            };
             s.insert(1, RefCell::new(l));
         }
         let mul = Mul(0, 1, 2);
-        mul.forward(&mut s);
+        mul.forward(&mut s).unwrap();
         {
             let mut res = s.get(&2).unwrap().borrow_mut();
             res.grad = 1.0;
         }
-        mul.backward(&mut s);
+        mul.backward(&mut s).unwrap();
         let r = s.get(&0).unwrap().borrow();
         let l = s.get(&1).unwrap().borrow();
         let res = s.get(&2).unwrap().borrow();
         assert_eq!(res.value, 2.0);
-        assert_eq!(res.is_need_grad, true);
+        assert!(res.is_need_grad);
         assert_eq!(res.grad, 1.0);
-        assert_eq!(r.is_need_grad, true);
-        assert_eq!(l.is_need_grad, true);
+        assert!(r.is_need_grad);
+        assert!(l.is_need_grad);
         assert_eq!(r.grad, 2.0);
         assert_eq!(l.grad, 1.0);
     }
@@ -102,24 +106,26 @@ mod tests {
     fn mul_self() {
         let mut s = Store::new();
         {
-            let mut r = Float::default();
-            r.value = 2.0;
-            r.is_need_grad = true;
+            let r = Float {
+                value: 2.0,
+                is_need_grad: true,
+                ..Default::default()
+            };
             s.insert(0, RefCell::new(r));
         }
         let mul = Mul(0, 0, 1);
-        mul.forward(&mut s);
+        mul.forward(&mut s).unwrap();
         {
             let mut res = s.get(&1).unwrap().borrow_mut();
             res.grad = 1.0;
         }
-        mul.backward(&mut s);
+        mul.backward(&mut s).unwrap();
         let r = s.get(&0).unwrap().borrow();
         let res = s.get(&1).unwrap().borrow();
         assert_eq!(res.value, 4.0);
-        assert_eq!(res.is_need_grad, true);
+        assert!(res.is_need_grad);
         assert_eq!(res.grad, 1.0);
-        assert_eq!(r.is_need_grad, true);
+        assert!(r.is_need_grad);
         assert_eq!(r.grad, 4.0);
     }
 }
@@ -1,21 +1,130 @@
+use crate::error::Result;
+use crate::lbfgs::LBFGS;
+use crate::scheduler::Scheduler;
 use std::collections::HashMap;
 pub enum Optimizer {
     SGD(SGD),
     Adam(Adam),
     AdaGrad(AdaGrad),
+    LBFGS(LBFGS),
+    Scheduled(Box<Scheduler>),
+    Vector(Box<dyn VectorOptimize>),
 }
-pub trait Optimize {
-    fn register(&mut self, _index: usize) {
-        return ();
+impl Optimizer {
+    // `None` for optimizers without a step size
+    pub fn learning_rate(&self) -> Option<f32> {
+        match self {
+            Optimizer::SGD(sgd) => Some(sgd.learning_rate),
+            Optimizer::Adam(adam) => Some(adam.learning_rate),
+            Optimizer::AdaGrad(adagrad) => Some(adagrad.learning_rate),
+            Optimizer::Scheduled(scheduler) => Some(scheduler.learning_rate()),
+            Optimizer::LBFGS(_) | Optimizer::Vector(_) => None,
+        }
+    }
+    pub fn set_learning_rate(&mut self, lr: f32) {
+        match self {
+            Optimizer::SGD(sgd) => sgd.learning_rate = lr,
+            Optimizer::Adam(adam) => adam.learning_rate = lr,
+            Optimizer::AdaGrad(adagrad) => adagrad.learning_rate = lr,
+            Optimizer::LBFGS(_) | Optimizer::Scheduled(_) | Optimizer::Vector(_) => (),
+        }
     }
+    // advances a learning-rate schedule by one iteration, no-op for unscheduled optimizers
+    pub fn schedule_step(&mut self, loss: f32) {
+        if let Optimizer::Scheduled(scheduler) = self {
+            scheduler.step(loss);
+        }
+    }
+}
+// applied to the gradients in `Executor::optimize` before the optimizer sees them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradClip {
+    // clamps every component to [-limit, limit]
+    Value(f32),
+    // rescales the whole gradient so that its l2 norm is at most `max_norm`
+    Norm(f32),
+}
+impl GradClip {
+    pub fn clip(&self, grads: &mut [f32]) {
+        match self {
+            GradClip::Value(limit) => grads.iter_mut().for_each(|g| *g = g.clamp(-limit, *limit)),
+            GradClip::Norm(max_norm) => {
+                let norm = grads.iter().map(|g| g * g).sum::<f32>().sqrt();
+                if norm > *max_norm {
+                    let scale = max_norm / norm;
+                    grads.iter_mut().for_each(|g| *g *= scale);
+                }
+            }
+        }
+    }
+}
+
+pub trait Optimize {
+    fn register(&mut self, _index: usize) {}
     fn optimize(&mut self, index: usize, old_value: f32, grad: f32) -> f32;
 }
+
+// re-evaluates (loss, gradients) at the given parameter values, used by line searches
+pub type Objective<'a> = dyn FnMut(&[f32]) -> Result<(f32, Vec<f32>)> + 'a;
+
+// optimizers that update the whole parameter vector at once,
+// `indices`, `values` and `grads` are aligned and sorted by node index
+pub trait VectorOptimize {
+    fn register(&mut self, _indices: &[usize]) {}
+    fn optimize_vector(
+        &mut self,
+        indices: &[usize],
+        values: &mut [f32],
+        loss: f32,
+        grads: &[f32],
+        objective: &mut Objective,
+    ) -> Result<()>;
+}
+
+fn register_per_scalar(opt: &mut dyn Optimize, indices: &[usize]) {
+    indices.iter().for_each(|index| opt.register(*index));
+}
+fn optimize_per_scalar(
+    opt: &mut dyn Optimize,
+    indices: &[usize],
+    values: &mut [f32],
+    grads: &[f32],
+) {
+    for ((index, value), grad) in indices.iter().zip(values.iter_mut()).zip(grads.iter()) {
+        *value = opt.optimize(*index, *value, *grad);
+    }
+}
+
+// adapter running a per-scalar optimizer through the vector interface
+pub struct PerScalar<O: Optimize>(pub O);
+impl<O: Optimize> VectorOptimize for PerScalar<O> {
+    fn register(&mut self, indices: &[usize]) {
+        register_per_scalar(&mut self.0, indices);
+    }
+    fn optimize_vector(
+        &mut self,
+        indices: &[usize],
+        values: &mut [f32],
+        _loss: f32,
+        grads: &[f32],
+        _objective: &mut Objective,
+    ) -> Result<()> {
+        optimize_per_scalar(&mut self.0, indices, values, grads);
+        return Ok(());
+    }
+}
+
+// one scalar at a time, as before the vector interface. LBFGS and `Vector` optimizers
+// need the whole vector and an objective, they leave the value unchanged here and are
+// driven through `VectorOptimize::optimize_vector` instead
 impl Optimize for Optimizer {
     fn register(&mut self, index: usize) {
         match self {
             Optimizer::SGD(sgd) => sgd.register(index),
             Optimizer::Adam(adam) => adam.register(index),
             Optimizer::AdaGrad(adagrad) => adagrad.register(index),
+            Optimizer::Scheduled(scheduler) => Optimize::register(scheduler.as_mut(), index),
+            Optimizer::LBFGS(_) | Optimizer::Vector(_) => (),
         }
     }
     fn optimize(&mut self, index: usize, old_value: f32, grad: f32) -> f32 {
@@ -23,10 +132,51 @@ impl Optimize for Optimizer {
             Optimizer::SGD(sgd) => return sgd.optimize(index, old_value, grad),
             Optimizer::Adam(adam) => return adam.optimize(index, old_value, grad),
             Optimizer::AdaGrad(adagrad) => return adagrad.optimize(index, old_value, grad),
+            Optimizer::Scheduled(scheduler) => return scheduler.optimize(index, old_value, grad),
+            Optimizer::LBFGS(_) | Optimizer::Vector(_) => return old_value,
         }
     }
 }
 
+impl VectorOptimize for Optimizer {
+    fn register(&mut self, indices: &[usize]) {
+        match self {
+            Optimizer::SGD(sgd) => register_per_scalar(sgd, indices),
+            Optimizer::Adam(adam) => register_per_scalar(adam, indices),
+            Optimizer::AdaGrad(adagrad) => register_per_scalar(adagrad, indices),
+            Optimizer::LBFGS(lbfgs) => lbfgs.register(indices),
+            Optimizer::Scheduled(scheduler) => {
+                VectorOptimize::register(scheduler.as_mut(), indices)
+            }
+            Optimizer::Vector(opt) => opt.register(indices),
+        }
+    }
+    fn optimize_vector(
+        &mut self,
+        indices: &[usize],
+        values: &mut [f32],
+        loss: f32,
+        grads: &[f32],
+        objective: &mut Objective,
+    ) -> Result<()> {
+        match self {
+            Optimizer::SGD(sgd) => optimize_per_scalar(sgd, indices, values, grads),
+            Optimizer::Adam(adam) => optimize_per_scalar(adam, indices, values, grads),
+            Optimizer::AdaGrad(adagrad) => optimize_per_scalar(adagrad, indices, values, grads),
+            Optimizer::LBFGS(lbfgs) => {
+                return lbfgs.optimize_vector(indices, values, loss, grads, objective)
+            }
+            Optimizer::Scheduled(scheduler) => {
+                return scheduler.optimize_vector(indices, values, loss, grads, objective)
+            }
+            Optimizer::Vector(opt) => {
+                return opt.optimize_vector(indices, values, loss, grads, objective)
+            }
+        }
+        return Ok(());
+    }
+}
+
 //SGD optimizer
 pub struct SGD {
     learning_rate: f32,
@@ -37,9 +187,7 @@ impl SGD {
     }
 }
 impl Optimize for SGD {
-    fn register(&mut self, _index: usize) {
-        return ();
-    }
+    fn register(&mut self, _index: usize) {}
     fn optimize(&mut self, _index: usize, old_value: f32, grad: f32) -> f32 {
         return old_value - self.learning_rate * grad;
     }
@@ -63,7 +211,7 @@ impl Optimize for AdaGrad {
         self.aux.entry(index).or_insert(0.0);
     }
     fn optimize(&mut self, index: usize, old_value: f32, grad: f32) -> f32 {
-        let h = self.aux.get(&index).unwrap().clone();
+        let h = *self.aux.get(&index).unwrap();
         let h2 = h + grad * grad;
         self.aux.insert(index, h2);
         return old_value - self.learning_rate / (h2.sqrt() + 1e-7) * grad;
@@ -92,10 +240,49 @@ impl Optimize for Adam {
         self.aux.entry(index).or_insert((0.0, 0.0));
     }
     fn optimize(&mut self, index: usize, old_value: f32, grad: f32) -> f32 {
-        let (old_vel, old_mov) = self.aux.get(&index).unwrap().clone();
+        let (old_vel, old_mov) = *self.aux.get(&index).unwrap();
         let vel = self.beta1 * old_vel + (1.0 - self.beta1) * grad;
         let mov = self.beta0 * old_mov + (1.0 - self.beta0) * grad;
         self.aux.insert(index, (vel, mov));
         return old_value - self.learning_rate * mov / (vel.sqrt() + 1e-8);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn grad_clip() {
+        let mut grads = [3.0, -0.5, -4.0];
+        GradClip::Value(1.0).clip(&mut grads);
+        assert_eq!(grads, [1.0, -0.5, -1.0]);
+        let mut grads = [3.0, -4.0];
+        GradClip::Norm(1.0).clip(&mut grads);
+        assert!((grads[0] - 0.6).abs() < 1e-6);
+        assert!((grads[1] - (-0.8)).abs() < 1e-6);
+        let mut grads = [0.3, -0.4];
+        GradClip::Norm(1.0).clip(&mut grads);
+        assert_eq!(grads, [0.3, -0.4]);
+    }
+    #[test]
+    fn per_scalar_adapter() {
+        let indices = [3, 7];
+        let mut values = [1.0, -2.0];
+        let grads = [0.5, -1.0];
+        let mut objective = |_: &[f32]| -> Result<(f32, Vec<f32>)> { unreachable!() };
+        let mut opt = PerScalar(SGD::new(0.1));
+        opt.register(&indices);
+        opt.optimize_vector(&indices, &mut values, 0.0, &grads, &mut objective)
+            .unwrap();
+        assert!((values[0] - 0.95).abs() < 1e-6);
+        assert!((values[1] - (-1.9)).abs() < 1e-6);
+    }
+    #[test]
+    fn per_scalar_optimizer() {
+        let mut opt = Optimizer::SGD(SGD::new(0.1));
+        Optimize::register(&mut opt, 3);
+        assert!((opt.optimize(3, 1.0, 0.5) - 0.95).abs() < 1e-6);
+        let mut opt = Optimizer::LBFGS(LBFGS::new(4));
+        assert_eq!(opt.optimize(3, 1.0, 0.5), 1.0);
+    }
+}
@@ -1,53 +1,901 @@
+use crate::coefficient::Coefficient;
+use crate::error::{FFError, Result};
 use crate::graph::{self, Graph, NodeAllocator};
-use std::collections::HashMap;
-#[derive(Debug)]
-pub struct Term {
-    coefficient: f32,
+use crate::loss::Loss;
+use crate::rational::Rational;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::{self, Display};
+use std::ops::{Add, Mul, Neg, Sub};
+// coefficients are f32 unless an exact type such as `Rational` is asked for
+#[derive(Debug, Clone)]
+pub struct Term<C: Coefficient = f32> {
+    coefficient: C,
     variables: HashMap<String, usize>,
 }
 impl Term {
     pub fn new() -> Self {
+        return Term::constant(1.0);
+    }
+}
+impl<C: Coefficient> Term<C> {
+    // `value` times no variables
+    pub fn constant(value: C) -> Self {
         Term {
-            coefficient: 1.0,
+            coefficient: value,
             variables: HashMap::new(),
         }
     }
-    pub fn set_coefficient(&mut self, cof: f32) {
+    pub fn set_coefficient(&mut self, cof: C) {
         self.coefficient = cof;
     }
     pub fn add_variable(&mut self, name: &str, order: usize) {
         self.variables
             .entry(name.to_string())
-            .and_modify(|e| *e = *e + order)
+            .and_modify(|e| *e += order)
             .or_insert(order);
     }
+    pub fn get_coefficient(&self) -> C {
+        return self.coefficient.clone();
+    }
+    pub fn get_variables(&self) -> &HashMap<String, usize> {
+        return &self.variables;
+    }
+    // order of `name` in this term, 0 if absent
+    pub fn degree(&self, name: &str) -> usize {
+        return self.variables.get(name).copied().unwrap_or(0);
+    }
+    pub fn total_degree(&self) -> usize {
+        return self.variables.values().sum();
+    }
+    fn mul_term(&self, other: &Term<C>) -> Term<C> {
+        let mut t = self.clone();
+        t.coefficient = t.coefficient * other.coefficient.clone();
+        other
+            .variables
+            .iter()
+            .for_each(|(name, order)| t.add_variable(name, *order));
+        return t;
+    }
+    // variables with a non-zero order, sorted by name, identifies like terms
+    fn monomial(&self) -> Vec<(String, usize)> {
+        let mut monomial = self
+            .variables
+            .iter()
+            .filter(|(_, order)| **order > 0)
+            .map(|(name, order)| (name.clone(), *order))
+            .collect::<Vec<(String, usize)>>();
+        monomial.sort();
+        return monomial;
+    }
+}
+// like terms with equal coefficients, order-0 variables are ignored
+impl<C: Coefficient> PartialEq for Term<C> {
+    fn eq(&self, other: &Term<C>) -> bool {
+        return self.coefficient == other.coefficient && self.monomial() == other.monomial();
+    }
 }
-#[derive(Debug)]
-pub struct Polynomial {
-    terms: Vec<Term>,
+impl<C: Coefficient> Default for Term<C> {
+    fn default() -> Self {
+        Self::constant(C::one())
+    }
+}
+// term orders for the canonical form, variables are ranked by name so that
+// with lex x > y > z
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MonomialOrder {
+    // compares exponents variable by variable
+    Lex,
+    // total degree first, ties broken by lex
+    GrLex,
+    // total degree first, ties broken by the smaller exponent in the last differing variable
+    GRevLex,
+}
+impl MonomialOrder {
+    pub fn compare<C: Coefficient>(&self, a: &Term<C>, b: &Term<C>) -> Ordering {
+        let (a, b) = (a.monomial(), b.monomial());
+        let names = a
+            .iter()
+            .chain(b.iter())
+            .map(|(name, _)| name.as_str())
+            .collect::<BTreeSet<&str>>();
+        let exponents = |m: &[(String, usize)]| {
+            names
+                .iter()
+                .map(|name| {
+                    m.iter()
+                        .find(|(n, _)| n == name)
+                        .map_or(0, |(_, order)| *order)
+                })
+                .collect::<Vec<usize>>()
+        };
+        return self.compare_exponents(&exponents(&a), &exponents(&b));
+    }
+    // exponent vectors over the same variables, sorted by name
+    pub fn compare_exponents(&self, ea: &[usize], eb: &[usize]) -> Ordering {
+        let total = ea.iter().sum::<usize>().cmp(&eb.iter().sum::<usize>());
+        return match self {
+            MonomialOrder::Lex => ea.cmp(eb),
+            MonomialOrder::GrLex => total.then_with(|| ea.cmp(eb)),
+            MonomialOrder::GRevLex => total.then_with(|| {
+                ea.iter()
+                    .zip(eb.iter())
+                    .rev()
+                    .find(|(x, y)| x != y)
+                    .map_or(Ordering::Equal, |(x, y)| y.cmp(x))
+            }),
+        };
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Polynomial<C: Coefficient = f32> {
+    terms: Vec<Term<C>>,
 }
 impl Polynomial {
     pub fn new() -> Self {
+        return Polynomial::zero();
+    }
+    pub fn constant(value: f32) -> Polynomial {
+        return Polynomial::from_terms(vec![Term::constant(value)]);
+    }
+    pub fn variable(name: &str) -> Polynomial {
+        let mut t = Term::new();
+        t.add_variable(name, 1);
+        return Polynomial { terms: vec![t] };
+    }
+    // polynomial in `name` from coefficients lowest degree first, zeros are skipped
+    pub fn from_univariate(name: &str, coefficients: &[f64]) -> Polynomial {
+        let mut res = Polynomial::new();
+        for (order, c) in coefficients.iter().enumerate() {
+            if *c == 0.0 {
+                continue;
+            }
+            let mut t = Term::new();
+            t.set_coefficient(*c as f32);
+            if order > 0 {
+                t.add_variable(name, order);
+            }
+            res.terms.push(t);
+        }
+        return res;
+    }
+    // parses conventional notation such as `3x^2*y - 2.5*y + 7 = 0`,
+    // an equation `lhs = rhs` becomes the polynomial `lhs - rhs`.
+    // variables are a letter followed by letters, digits or `_`, so `xy` is a single
+    // variable and a product is written `x*y` or `x y`
+    pub fn parse(text: &str) -> Result<Polynomial> {
+        return Parser::new(text).equation();
+    }
+}
+impl Polynomial<Rational> {
+    // same grammar as `Polynomial::parse` with every literal read exactly, so that
+    // `0.1x` has the coefficient 1/10
+    pub fn parse_exact(text: &str) -> Result<Polynomial<Rational>> {
+        return Parser::new(text).equation();
+    }
+}
+impl<C: Coefficient> Polynomial<C> {
+    pub fn zero() -> Self {
         Polynomial { terms: Vec::new() }
     }
-    pub fn add_term(&mut self, t: Term) {
+    pub fn from_terms(terms: Vec<Term<C>>) -> Self {
+        Polynomial { terms }
+    }
+    pub fn add_term(&mut self, t: Term<C>) {
         self.terms.push(t);
     }
+    pub fn get_terms(&self) -> &Vec<Term<C>> {
+        return &self.terms;
+    }
+    // same polynomial with `f` applied to every coefficient
+    pub fn map_coefficients<D: Coefficient>(&self, f: impl Fn(&C) -> D) -> Polynomial<D> {
+        let terms = self
+            .terms
+            .iter()
+            .map(|t| Term {
+                coefficient: f(&t.coefficient),
+                variables: t.variables.clone(),
+            })
+            .collect();
+        return merge_like_terms(Polynomial { terms });
+    }
+    // exact copy with every f32 coefficient read as the shortest decimal it prints as
+    pub fn to_exact(&self) -> Result<Polynomial<Rational>> {
+        let mut res = Polynomial::zero();
+        for t in self.terms.iter() {
+            let mut exact = Term::constant(t.coefficient.to_rational()?);
+            exact.variables = t.variables.clone();
+            res.add_term(exact);
+        }
+        return Ok(res);
+    }
+    // nearest f32 coefficients, what the graph solvers work with
+    pub fn to_float(&self) -> Polynomial {
+        return self.map_coefficients(|c| c.to_f32());
+    }
+    // merges like terms, drops zero coefficients and order-0 variables,
+    // and sorts the terms from the leading term down
+    pub fn normalize(&mut self, order: MonomialOrder) {
+        let mut res = merge_like_terms(std::mem::take(self));
+        res.terms.sort_by(|a, b| order.compare(b, a));
+        *self = res;
+    }
+    pub fn normalized(&self, order: MonomialOrder) -> Polynomial<C> {
+        let mut res = self.clone();
+        res.normalize(order);
+        return res;
+    }
+    pub fn is_zero(&self) -> bool {
+        return self.terms.iter().all(|t| t.coefficient.is_zero());
+    }
+    // highest order of `name` over all terms
+    pub fn degree(&self, name: &str) -> usize {
+        return self
+            .terms
+            .iter()
+            .filter(|t| !t.coefficient.is_zero())
+            .map(|t| t.degree(name))
+            .max()
+            .unwrap_or(0);
+    }
+    pub fn total_degree(&self) -> usize {
+        return self
+            .terms
+            .iter()
+            .filter(|t| !t.coefficient.is_zero())
+            .map(|t| t.total_degree())
+            .max()
+            .unwrap_or(0);
+    }
+    // names of the variables that appear with a non-zero order, sorted
+    pub fn variables(&self) -> Vec<String> {
+        return self
+            .terms
+            .iter()
+            .filter(|t| !t.coefficient.is_zero())
+            .flat_map(|t| t.monomial().into_iter().map(|(name, _)| name))
+            .collect::<BTreeSet<String>>()
+            .into_iter()
+            .collect();
+    }
+    // greatest term under `order` after merging like terms, `None` for the zero polynomial
+    pub fn leading_term(&self, order: MonomialOrder) -> Option<Term<C>> {
+        return self.normalized(order).terms.into_iter().next();
+    }
+    pub fn scale(&self, factor: C) -> Polynomial<C> {
+        let mut res = self.clone();
+        res.terms
+            .iter_mut()
+            .for_each(|t| t.coefficient = t.coefficient.clone() * factor.clone());
+        return merge_like_terms(res);
+    }
+    // replaces `name` with `replacement`, e.g. x -> (y + 1) in x^2 gives y^2 + 2y + 1
+    pub fn substitute(&self, name: &str, replacement: &Polynomial<C>) -> Polynomial<C> {
+        return self.compose(&HashMap::from([(name.to_string(), replacement.clone())]));
+    }
+    // replaces every variable in `replacements` at once, other variables are kept
+    pub fn compose(&self, replacements: &HashMap<String, Polynomial<C>>) -> Polynomial<C> {
+        // powers[name][k] = replacement^k, grown on demand
+        let mut powers = HashMap::<&str, Vec<Polynomial<C>>>::new();
+        let mut res = Polynomial::zero();
+        let one = || Polynomial::from_terms(vec![Term::constant(C::one())]);
+        for t in self.terms.iter() {
+            let mut kept = Term::constant(t.coefficient.clone());
+            let mut product = one();
+            for (name, order) in t.variables.iter() {
+                match replacements.get(name) {
+                    Some(replacement) => {
+                        let cache = powers.entry(name.as_str()).or_insert_with(|| vec![one()]);
+                        while cache.len() <= *order {
+                            let next = cache.last().unwrap() * replacement;
+                            cache.push(next);
+                        }
+                        product = &product * &cache[*order];
+                    }
+                    None => kept.add_variable(name, *order),
+                }
+            }
+            res.terms
+                .extend(product.terms.iter().map(|p| p.mul_term(&kept)));
+        }
+        return merge_like_terms(res);
+    }
+    // coefficients of a polynomial in `name` only, lowest degree first
+    pub fn univariate_coefficients(&self, name: &str) -> Result<Vec<f64>> {
+        let mut coefficients = vec![0.0; self.degree(name) + 1];
+        // zero terms are skipped, as degree() does not count them
+        for t in self.terms.iter().filter(|t| !t.coefficient.is_zero()) {
+            if let Some((other, _)) = t.monomial().iter().find(|(n, _)| n != name) {
+                return Err(FFError::Polynomial(format!(
+                    "expected a polynomial in {} only, found {}",
+                    name, other
+                )));
+            }
+            coefficients[t.degree(name)] += t.coefficient.to_f64();
+        }
+        return Ok(coefficients);
+    }
+    // coefficients in the only variable of the polynomial, lowest degree first,
+    // a constant polynomial gives a single coefficient
+    pub fn univariate(&self) -> Result<Vec<f64>> {
+        let variables = self.variables();
+        return match variables.len() {
+            0 => Ok(vec![self
+                .terms
+                .iter()
+                .map(|t| t.coefficient.to_f64())
+                .sum()]),
+            1 => self.univariate_coefficients(&variables[0]),
+            _ => Err(FFError::Polynomial(format!(
+                "expected a univariate polynomial, found variables {:?}",
+                variables
+            ))),
+        };
+    }
+    // evaluates in f64, with Horner's scheme for univariate polynomials
+    pub fn evaluate(&self, values: &HashMap<String, f64>) -> Result<f64> {
+        let variables = self.variables();
+        let value_of = |name: &String| {
+            values
+                .get(name)
+                .copied()
+                .ok_or(FFError::Store(format!("not find key: {}", name)))
+        };
+        if variables.len() == 1 {
+            let x = value_of(&variables[0])?;
+            let coefficients = self.univariate_coefficients(&variables[0])?;
+            return Ok(horner(&coefficients, x));
+        }
+        let mut res = 0.0;
+        for t in self.terms.iter() {
+            let mut product = t.coefficient.to_f64();
+            for (name, order) in t.variables.iter() {
+                product *= value_of(name)?.powi(*order as i32);
+            }
+            res += product;
+        }
+        return Ok(res);
+    }
+    // partial derivative with respect to `name`
+    pub fn derivative(&self, name: &str) -> Polynomial<C> {
+        let mut res = Polynomial::zero();
+        for t in self.terms.iter() {
+            let order = t.degree(name);
+            if order == 0 {
+                continue;
+            }
+            let mut d = t.clone();
+            d.coefficient = d.coefficient * C::from_usize(order);
+            d.variables.insert(name.to_string(), order - 1);
+            res.add_term(d);
+        }
+        return merge_like_terms(res);
+    }
+    // partial derivatives with respect to every variable of the polynomial
+    pub fn gradient(&self) -> HashMap<String, Polynomial<C>> {
+        return self
+            .variables()
+            .into_iter()
+            .map(|name| {
+                let d = self.derivative(&name);
+                (name, d)
+            })
+            .collect();
+    }
+    // plain-text rendering that `Polynomial::parse` reads back, e.g. `3x^2*y - 2.5y + 7`
+    pub fn to_ascii(&self) -> String {
+        return render(self, Style::Ascii);
+    }
+    pub fn to_latex(&self) -> String {
+        return render(self, Style::Latex);
+    }
+    // coefficients are rounded to f32 when lowered to the graph
     pub fn tranform_to_graph(
         &self,
         name_to_index: &mut HashMap<String, usize>,
         allocator: &mut NodeAllocator,
+    ) -> Graph {
+        return self.tranform_to_weighted_graph(1.0, Loss::Squared, name_to_index, allocator);
+    }
+    // loss(factor * residual), see `EquationScaling::factors`
+    pub fn tranform_to_weighted_graph(
+        &self,
+        factor: f32,
+        loss: Loss,
+        name_to_index: &mut HashMap<String, usize>,
+        allocator: &mut NodeAllocator,
     ) -> Graph {
         let mut g = Graph::new();
         let output = tranform_polynomial_to_graph(self, &mut g, name_to_index, allocator);
-        let loss = g.mul(output, output, allocator);
+        let loss = scaled_loss(output, factor, loss, &mut g, allocator);
         g.output(loss);
         return g;
     }
+    // graph whose output is the residual itself, used for jacobians
+    pub fn tranform_to_residual_graph(
+        &self,
+        name_to_index: &mut HashMap<String, usize>,
+        allocator: &mut NodeAllocator,
+    ) -> Graph {
+        let mut g = Graph::new();
+        let output = tranform_polynomial_to_graph(self, &mut g, name_to_index, allocator);
+        g.output(output);
+        return g;
+    }
+}
+
+// one graph whose output is the sum of squared residuals of all equations,
+// so that vector optimizers see the whole system at once
+pub fn tranform_system_to_graph<C: Coefficient>(
+    polys: &[Polynomial<C>],
+    name_to_index: &mut HashMap<String, usize>,
+    allocator: &mut NodeAllocator,
+) -> Graph {
+    let mut g = Graph::new();
+    let losses = polys
+        .iter()
+        .map(|poly| {
+            let output = tranform_polynomial_to_graph(poly, &mut g, name_to_index, allocator);
+            return g.loss(output, Loss::Squared, allocator);
+        })
+        .collect::<Vec<usize>>();
+    let loss = graph::sum(&losses, &mut g, allocator);
+    g.output(loss);
+    return g;
+}
+
+// sum of loss(factors[i] * residual_i), one factor per equation. residuals of a
+// data fit come from `loss::data_residuals`
+pub fn tranform_weighted_system_to_graph<C: Coefficient>(
+    polys: &[Polynomial<C>],
+    factors: &[f32],
+    loss: Loss,
+    name_to_index: &mut HashMap<String, usize>,
+    allocator: &mut NodeAllocator,
+) -> Result<Graph> {
+    let mut g = Graph::new();
+    let losses = weighted_losses(polys, factors, loss, &mut g, name_to_index, allocator)?;
+    let loss = graph::sum(&losses, &mut g, allocator);
+    g.output(loss);
+    return Ok(g);
+}
+
+// the loss node of every equation, for graphs that add further terms
+pub(crate) fn weighted_losses<C: Coefficient>(
+    polys: &[Polynomial<C>],
+    factors: &[f32],
+    loss: Loss,
+    g: &mut Graph,
+    name_to_index: &mut HashMap<String, usize>,
+    allocator: &mut NodeAllocator,
+) -> Result<Vec<usize>> {
+    if factors.len() != polys.len() {
+        return Err(FFError::Polynomial(format!(
+            "{} factors for {} equations",
+            factors.len(),
+            polys.len()
+        )));
+    }
+    return Ok(polys
+        .iter()
+        .zip(factors.iter())
+        .map(|(poly, factor)| {
+            let output = tranform_polynomial_to_graph(poly, g, name_to_index, allocator);
+            return scaled_loss(output, *factor, loss, g, allocator);
+        })
+        .collect());
+}
+
+// equal when the canonical forms match, independent of term order and like-term splits
+impl<C: Coefficient> PartialEq for Polynomial<C> {
+    fn eq(&self, other: &Polynomial<C>) -> bool {
+        return self.normalized(MonomialOrder::Lex).terms
+            == other.normalized(MonomialOrder::Lex).terms;
+    }
+}
+
+impl<C: Coefficient> Default for Polynomial<C> {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl<C: Coefficient> Add<&Polynomial<C>> for &Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn add(self, rhs: &Polynomial<C>) -> Polynomial<C> {
+        let mut res = self.clone();
+        res.terms.extend(rhs.terms.iter().cloned());
+        return merge_like_terms(res);
+    }
+}
+impl<C: Coefficient> Sub<&Polynomial<C>> for &Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn sub(self, rhs: &Polynomial<C>) -> Polynomial<C> {
+        return self + &(-rhs);
+    }
+}
+impl<C: Coefficient> Mul<&Polynomial<C>> for &Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn mul(self, rhs: &Polynomial<C>) -> Polynomial<C> {
+        let mut res = Polynomial::zero();
+        for l in self.terms.iter() {
+            res.terms.extend(rhs.terms.iter().map(|r| l.mul_term(r)));
+        }
+        return merge_like_terms(res);
+    }
+}
+impl Mul<f32> for &Polynomial {
+    type Output = Polynomial;
+    fn mul(self, rhs: f32) -> Polynomial {
+        return self.scale(rhs);
+    }
+}
+impl<C: Coefficient> Neg for &Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn neg(self) -> Polynomial<C> {
+        return self.scale(-C::one());
+    }
+}
+impl<C: Coefficient> Add for Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn add(self, rhs: Polynomial<C>) -> Polynomial<C> {
+        return &self + &rhs;
+    }
+}
+impl<C: Coefficient> Sub for Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn sub(self, rhs: Polynomial<C>) -> Polynomial<C> {
+        return &self - &rhs;
+    }
+}
+impl<C: Coefficient> Mul for Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn mul(self, rhs: Polynomial<C>) -> Polynomial<C> {
+        return &self * &rhs;
+    }
+}
+impl Mul<f32> for Polynomial {
+    type Output = Polynomial;
+    fn mul(self, rhs: f32) -> Polynomial {
+        return self.scale(rhs);
+    }
+}
+impl<C: Coefficient> Neg for Polynomial<C> {
+    type Output = Polynomial<C>;
+    fn neg(self) -> Polynomial<C> {
+        return self.scale(-C::one());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    Unicode,
+    Ascii,
+    Latex,
+}
+// terms in graded lex order, leading term first
+fn render<C: Coefficient>(poly: &Polynomial<C>, style: Style) -> String {
+    let poly = poly.normalized(MonomialOrder::GrLex);
+    if poly.terms.is_empty() {
+        return "0".to_string();
+    }
+    let minus = if style == Style::Unicode {
+        "\u{2212}"
+    } else {
+        "-"
+    };
+    let mut res = String::new();
+    for (i, t) in poly.terms.iter().enumerate() {
+        let negative = t.coefficient.is_negative();
+        match (i, negative) {
+            (0, true) => res.push_str(minus),
+            (0, false) => (),
+            (_, true) => res.push_str(&format!(" {} ", minus)),
+            (_, false) => res.push_str(" + "),
+        }
+        res.push_str(&render_term(t, &t.coefficient.abs(), style));
+    }
+    return res;
+}
+// renders `magnitude` times the variables of `t`
+fn render_term<C: Coefficient>(t: &Term<C>, magnitude: &C, style: Style) -> String {
+    let monomial = t.monomial();
+    let mut res = String::new();
+    if !magnitude.is_one() || monomial.is_empty() {
+        match style {
+            Style::Latex => res.push_str(&magnitude.to_latex()),
+            _ => res.push_str(&magnitude.to_string()),
+        }
+    }
+    // `1/3x` would read as 1 / (3x)
+    let fraction = res.contains('/');
+    // single-letter names can be juxtaposed, longer ones need a product sign
+    let juxtapose = monomial.iter().all(|(name, _)| name.chars().count() == 1);
+    for (i, (name, order)) in monomial.iter().enumerate() {
+        let after_coefficient = i == 0 && !res.is_empty();
+        let separator = match style {
+            Style::Unicode if !juxtapose && i > 0 => "\u{b7}",
+            Style::Ascii if i > 0 => "*",
+            // keeps `2e1` from reading back as scientific notation
+            Style::Ascii if after_coefficient && name.starts_with(['e', 'E']) => "*",
+            Style::Ascii if after_coefficient && fraction => "*",
+            Style::Unicode if after_coefficient && fraction => " ",
+            Style::Latex if !juxtapose && (i > 0 || after_coefficient) => " \\cdot ",
+            _ => "",
+        };
+        res.push_str(separator);
+        res.push_str(&render_variable(name, style));
+        if *order > 1 {
+            res.push_str(&render_exponent(*order, style));
+        }
+    }
+    return res;
+}
+fn render_variable(name: &str, style: Style) -> String {
+    if style != Style::Latex {
+        return name.to_string();
+    }
+    if let Some((base, subscript)) = name.split_once('_') {
+        return format!("{}_{{{}}}", base, subscript);
+    }
+    if name.chars().count() > 1 {
+        return format!("\\mathrm{{{}}}", name);
+    }
+    return name.to_string();
+}
+const SUPERSCRIPTS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+fn render_exponent(order: usize, style: Style) -> String {
+    return match style {
+        Style::Unicode => order
+            .to_string()
+            .chars()
+            .map(|c| SUPERSCRIPTS[c.to_digit(10).unwrap() as usize])
+            .collect(),
+        Style::Ascii => format!("^{}", order),
+        Style::Latex => format!("^{{{}}}", order),
+    };
+}
+// unicode math such as `3x²y − 2.5y + 7`, `{:#}` gives the ascii form
+impl<C: Coefficient> Display for Polynomial<C> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let style = if fmt.alternate() {
+            Style::Ascii
+        } else {
+            Style::Unicode
+        };
+        return write!(fmt, "{}", render(self, style));
+    }
+}
+impl<C: Coefficient> Display for Term<C> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let style = if fmt.alternate() {
+            Style::Ascii
+        } else {
+            Style::Unicode
+        };
+        let minus = match (self.coefficient.is_negative(), style) {
+            (false, _) => "",
+            (true, Style::Unicode) => "\u{2212}",
+            (true, _) => "-",
+        };
+        return write!(
+            fmt,
+            "{}{}",
+            minus,
+            render_term(self, &self.coefficient.abs(), style)
+        );
+    }
+}
+
+// coefficients lowest degree first
+pub fn horner(coefficients: &[f64], x: f64) -> f64 {
+    return coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c);
+}
+
+// merges terms with the same variables (in order of first appearance) and drops zero terms
+fn merge_like_terms<C: Coefficient>(poly: Polynomial<C>) -> Polynomial<C> {
+    let mut positions = HashMap::<Vec<(String, usize)>, usize>::new();
+    let mut terms = Vec::<Term<C>>::new();
+    for t in poly.terms.into_iter() {
+        let monomial = t.monomial();
+        match positions.get(&monomial) {
+            Some(i) => {
+                let sum = terms[*i].coefficient.clone() + t.coefficient;
+                terms[*i].coefficient = sum;
+            }
+            None => {
+                positions.insert(monomial.clone(), terms.len());
+                let mut merged = Term::constant(t.coefficient);
+                monomial
+                    .iter()
+                    .for_each(|(name, order)| merged.add_variable(name, *order));
+                terms.push(merged);
+            }
+        }
+    }
+    terms.retain(|t| !t.coefficient.is_zero());
+    return Polynomial { terms };
+}
+
+// recursive descent parser behind `Polynomial::parse` and `Polynomial::parse_exact`
+//   equation := expr ('=' expr)?
+//   expr     := ('+' | '-')* term (('+' | '-')+ term)*
+//   term     := factor ('*'? factor)*
+//   factor   := (number | variable) ('^' integer)?
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+impl Parser {
+    fn new(text: &str) -> Self {
+        Parser {
+            chars: text.chars().collect(),
+            pos: 0,
+        }
+    }
+    fn error(&self, pos: usize, info: &str) -> FFError {
+        let found = match self.chars.get(pos) {
+            Some(c) => format!("'{}'", c),
+            None => "end of input".to_string(),
+        };
+        return FFError::Parse(format!("column {}: {}, found {}", pos + 1, info, found));
+    }
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        return self.chars.get(self.pos).copied();
+    }
+    fn equation<C: Coefficient>(&mut self) -> Result<Polynomial<C>> {
+        let mut poly = self.expr()?;
+        if self.peek() == Some('=') {
+            self.pos += 1;
+            for mut t in self.expr::<C>()?.terms.into_iter() {
+                t.coefficient = -t.coefficient;
+                poly.add_term(t);
+            }
+        }
+        if self.peek().is_some() {
+            return Err(self.error(self.pos, "expected an operator"));
+        }
+        return Ok(poly);
+    }
+    fn expr<C: Coefficient>(&mut self) -> Result<Polynomial<C>> {
+        let mut poly = Polynomial::zero();
+        let mut first = true;
+        loop {
+            let mut negative = false;
+            let mut has_sign = false;
+            while let Some(c @ ('+' | '-')) = self.peek() {
+                negative ^= c == '-';
+                has_sign = true;
+                self.pos += 1;
+            }
+            if !first && !has_sign {
+                return Ok(poly);
+            }
+            let mut t = self.term::<C>()?;
+            if negative {
+                t.coefficient = -t.coefficient;
+            }
+            poly.add_term(t);
+            first = false;
+        }
+    }
+    fn term<C: Coefficient>(&mut self) -> Result<Term<C>> {
+        let mut t = Term::constant(C::one());
+        self.factor(&mut t)?;
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.pos += 1;
+                    self.factor(&mut t)?;
+                }
+                Some(c) if c.is_ascii_digit() || c == '.' || c.is_alphabetic() => {
+                    self.factor(&mut t)?
+                }
+                _ => return Ok(t),
+            }
+        }
+    }
+    fn factor<C: Coefficient>(&mut self, t: &mut Term<C>) -> Result<()> {
+        match self.peek() {
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let value = self.number::<C>()?;
+                let order = self.exponent()?;
+                t.coefficient = t.coefficient.clone() * value.pow(order);
+            }
+            Some(c) if c.is_alphabetic() => {
+                let name = self.variable();
+                let order = self.exponent()?;
+                if order > 0 {
+                    t.add_variable(&name, order);
+                }
+            }
+            _ => return Err(self.error(self.pos, "expected a number or a variable")),
+        }
+        return Ok(());
+    }
+    fn number<C: Coefficient>(&mut self) -> Result<C> {
+        let start = self.pos;
+        let digits = |p: &mut Parser| {
+            while p.chars.get(p.pos).is_some_and(|c| c.is_ascii_digit()) {
+                p.pos += 1;
+            }
+        };
+        digits(self);
+        if self.chars.get(self.pos) == Some(&'.') {
+            self.pos += 1;
+            digits(self);
+        }
+        // scientific notation only when a digit follows, so that `2e` stays `2 * e`
+        if let Some('e' | 'E') = self.chars.get(self.pos) {
+            let mut p = self.pos + 1;
+            if let Some('+' | '-') = self.chars.get(p) {
+                p += 1;
+            }
+            if self.chars.get(p).is_some_and(|c| c.is_ascii_digit()) {
+                self.pos = p;
+                digits(self);
+            }
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        return C::parse(&text)
+            .map_err(|_| self.error(start, &format!("invalid number '{}'", text)));
+    }
+    fn variable(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+        {
+            self.pos += 1;
+        }
+        return self.chars[start..self.pos].iter().collect();
+    }
+    fn exponent(&mut self) -> Result<usize> {
+        if self.peek() != Some('^') {
+            return Ok(1);
+        }
+        self.pos += 1;
+        self.skip_whitespace();
+        let start = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error(start, "expected a non-negative integer exponent"));
+        }
+        let text = self.chars[start..self.pos].iter().collect::<String>();
+        return text
+            .parse::<usize>()
+            .map_err(|_| self.error(start, &format!("invalid exponent '{}'", text)));
+    }
+}
+
+fn scaled_loss(
+    output: usize,
+    factor: f32,
+    loss: Loss,
+    g: &mut Graph,
+    allocator: &mut NodeAllocator,
+) -> usize {
+    let residual = if factor == 1.0 {
+        output
+    } else {
+        let cof = g.constant(factor, allocator);
+        g.mul(output, cof, allocator)
+    };
+    return g.loss(residual, loss, allocator);
 }
 
-fn tranform_polynomial_to_graph(
-    poly: &Polynomial,
+pub(crate) fn tranform_polynomial_to_graph<C: Coefficient>(
+    poly: &Polynomial<C>,
     g: &mut Graph,
     name_to_index: &mut HashMap<String, usize>,
     allocator: &mut NodeAllocator,
@@ -68,12 +916,295 @@ fn tranform_polynomial_to_graph(
                     return graph::binary_exponentiation(*var, *order, g, allocator);
                 })
                 .collect::<Vec<usize>>();
-            if t.coefficient != 1.0 || var_exps.len() == 0 {
-                let cof = g.constant(t.coefficient, allocator);
+            if !t.coefficient.is_one() || var_exps.is_empty() {
+                let cof = g.constant(t.coefficient.to_f32(), allocator);
                 var_exps.push(cof);
             }
             return graph::mul(&var_exps, g, allocator);
         })
         .collect::<Vec<usize>>();
+    // a zero polynomial, e.g. an equation scaled by a zero weight, has no terms
+    if term_results.is_empty() {
+        return g.constant(0.0, allocator);
+    }
     return graph::sum(&term_results, g, allocator);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn terms(poly: &Polynomial) -> Vec<(f32, Vec<(String, usize)>)> {
+        return poly
+            .terms
+            .iter()
+            .map(|t| {
+                let mut vars = t
+                    .variables
+                    .iter()
+                    .map(|(name, order)| (name.clone(), *order))
+                    .collect::<Vec<(String, usize)>>();
+                vars.sort();
+                (t.coefficient, vars)
+            })
+            .collect();
+    }
+    #[test]
+    fn parse() {
+        let poly = Polynomial::parse("3x^2*y - 2.5*y + 7 = 0").unwrap();
+        assert_eq!(
+            terms(&poly),
+            vec![
+                (3.0, vec![("x".to_string(), 2), ("y".to_string(), 1)]),
+                (-2.5, vec![("y".to_string(), 1)]),
+                (7.0, vec![]),
+                (-0.0, vec![]),
+            ]
+        );
+        let poly = Polynomial::parse("-x y x + 2^3 = y_1 - 1e-1").unwrap();
+        assert_eq!(
+            terms(&poly),
+            vec![
+                (-1.0, vec![("x".to_string(), 2), ("y".to_string(), 1)]),
+                (8.0, vec![]),
+                (-1.0, vec![("y_1".to_string(), 1)]),
+                (0.1, vec![]),
+            ]
+        );
+    }
+    fn assert_same(a: &Polynomial, b: &Polynomial) {
+        let (mut a, mut b) = (terms(a), terms(b));
+        a.sort_by(|l, r| l.1.cmp(&r.1));
+        b.sort_by(|l, r| l.1.cmp(&r.1));
+        assert_eq!(a, b);
+    }
+    #[test]
+    fn arithmetic() {
+        let p = Polynomial::parse("x + 2y").unwrap();
+        let q = Polynomial::parse("x - y + 1").unwrap();
+        assert_same(&(&p + &q), &Polynomial::parse("2x + y + 1").unwrap());
+        assert_same(&(&p - &q), &Polynomial::parse("3y - 1").unwrap());
+        assert_same(
+            &(&p * &q),
+            &Polynomial::parse("x^2 + x*y - 2y^2 + x + 2y").unwrap(),
+        );
+        assert_same(&(-&p), &Polynomial::parse("-x - 2y").unwrap());
+        assert_same(&(p.clone() * 0.5), &Polynomial::parse("0.5x + y").unwrap());
+        assert!((p.clone() - p).terms.is_empty());
+    }
+    #[test]
+    fn substitute() {
+        let p = Polynomial::parse("x^2 y + 3x").unwrap();
+        let res = p.substitute("x", &Polynomial::parse("z + 1").unwrap());
+        assert_same(
+            &res,
+            &Polynomial::parse("z^2 y + 2z y + y + 3z + 3").unwrap(),
+        );
+        let swapped = Polynomial::parse("x - 2y")
+            .unwrap()
+            .compose(&HashMap::from([
+                ("x".to_string(), Polynomial::variable("y")),
+                ("y".to_string(), Polynomial::variable("x")),
+            ]));
+        assert_same(&swapped, &Polynomial::parse("y - 2x").unwrap());
+    }
+    #[test]
+    fn canonical_form() {
+        let mut p = Polynomial::new();
+        let mut t = Term::new();
+        t.set_coefficient(2.0);
+        t.add_variable("y", 1);
+        t.add_variable("z", 0);
+        p.add_term(t);
+        p.add_term(Polynomial::parse("x^2 - y + 0x").unwrap().terms.remove(0));
+        p.add_term(Polynomial::parse("-y").unwrap().terms.remove(0));
+        p.add_term(Polynomial::parse("0 x y").unwrap().terms.remove(0));
+        p.normalize(MonomialOrder::Lex);
+        assert_eq!(
+            terms(&p),
+            vec![
+                (1.0, vec![("x".to_string(), 2)]),
+                (1.0, vec![("y".to_string(), 1)])
+            ]
+        );
+        assert_eq!(p, Polynomial::parse("y + x^2").unwrap());
+        assert_ne!(p, Polynomial::parse("y + x^3").unwrap());
+        assert_eq!(p.variables(), vec!["x", "y"]);
+        assert_eq!(Polynomial::parse("0 = 0").unwrap(), Polynomial::new());
+    }
+    #[test]
+    fn monomial_orders() {
+        let p = Polynomial::parse("x y^2 + x^2 + y^3 + x z").unwrap();
+        let order = |order: MonomialOrder| {
+            p.normalized(order)
+                .terms
+                .iter()
+                .map(|t| t.monomial())
+                .collect::<Vec<Vec<(String, usize)>>>()
+        };
+        let m = |text: &str| Polynomial::parse(text).unwrap().terms[0].monomial();
+        assert_eq!(
+            order(MonomialOrder::Lex),
+            vec![m("x^2"), m("x y^2"), m("x z"), m("y^3")]
+        );
+        assert_eq!(
+            order(MonomialOrder::GrLex),
+            vec![m("x y^2"), m("y^3"), m("x^2"), m("x z")]
+        );
+        assert_eq!(
+            order(MonomialOrder::GRevLex),
+            vec![m("x y^2"), m("y^3"), m("x^2"), m("x z")]
+        );
+        let q = Polynomial::parse("x z^2 + y^2 z").unwrap();
+        assert_eq!(
+            q.leading_term(MonomialOrder::GrLex).unwrap().monomial(),
+            m("x z^2")
+        );
+        assert_eq!(
+            q.leading_term(MonomialOrder::GRevLex).unwrap().monomial(),
+            m("y^2 z")
+        );
+        assert_eq!(p.total_degree(), 3);
+        assert_eq!(p.degree("y"), 3);
+        assert_eq!(p.degree("w"), 0);
+    }
+    #[test]
+    fn evaluate() {
+        let p = Polynomial::parse("2x^3 - 3x + 1").unwrap();
+        let at = |x: f64| HashMap::from([("x".to_string(), x)]);
+        assert_eq!(p.evaluate(&at(2.0)).unwrap(), 11.0);
+        assert_eq!(
+            p.univariate_coefficients("x").unwrap(),
+            vec![1.0, -3.0, 0.0, 2.0]
+        );
+        let q = Polynomial::parse("x^2 y - y + 4").unwrap();
+        let values = HashMap::from([("x".to_string(), 3.0), ("y".to_string(), 0.5)]);
+        assert_eq!(q.evaluate(&values).unwrap(), 8.0);
+        assert!(q.evaluate(&at(1.0)).is_err());
+        assert!(q.univariate_coefficients("x").is_err());
+        let r = Polynomial::parse("0 x^3 + x").unwrap();
+        assert_eq!(r.univariate_coefficients("x").unwrap(), vec![0.0, 1.0]);
+    }
+    #[test]
+    fn derivative() {
+        let p = Polynomial::parse("x^3 y^2 - 4x y + 7y + 2").unwrap();
+        assert_eq!(
+            p.derivative("x"),
+            Polynomial::parse("3x^2 y^2 - 4y").unwrap()
+        );
+        let gradient = p.gradient();
+        assert_eq!(gradient.len(), 2);
+        assert_eq!(gradient["y"], Polynomial::parse("2x^3 y - 4x + 7").unwrap());
+        assert!(p.derivative("z").is_zero());
+    }
+    #[test]
+    fn autodiff_matches_symbolic_derivative() {
+        use crate::executor::Executor;
+        use crate::optimizer::{Optimizer, SGD};
+        use crate::store::Store;
+        let p = Polynomial::parse("x^3 y^2 - 4x y + 7y + 2").unwrap();
+        let mut allocator = NodeAllocator::new();
+        let mut name_record = HashMap::new();
+        let graph = p.tranform_to_residual_graph(&mut name_record, &mut allocator);
+        let mut exec = Executor::new(graph, Optimizer::SGD(SGD::new(0.0)));
+        let mut s = Store::new();
+        let init = HashMap::from([("x".to_string(), 1.5), ("y".to_string(), -0.5)]);
+        exec.init_parameter(&init, &mut s).unwrap();
+        exec.init_contants(&mut s);
+        exec.forward(&mut s).unwrap();
+        exec.backward(&mut s).unwrap();
+        let values = init
+            .iter()
+            .map(|(k, v)| (k.clone(), *v as f64))
+            .collect::<HashMap<String, f64>>();
+        let output = exec.get_output_value(&mut s).unwrap().value as f64;
+        assert!((output - p.evaluate(&values).unwrap()).abs() < 1e-5);
+        for (name, d) in p.gradient() {
+            let grad = exec.get_value(name_record[&name], &mut s).unwrap().grad as f64;
+            assert!((grad - d.evaluate(&values).unwrap()).abs() < 1e-5);
+        }
+    }
+    #[test]
+    fn exact_coefficients() {
+        use crate::executor::Executor;
+        use crate::optimizer::{Optimizer, SGD};
+        use crate::store::Store;
+        let mut p = Polynomial::<Rational>::parse_exact("x + 0.1").unwrap();
+        p = p.scale(Rational::new(1, 3));
+        assert_eq!(p.to_string(), "1/3 x + 1/30");
+        assert_eq!(p.to_ascii(), "1/3*x + 1/30");
+        assert_eq!(p.to_latex(), "\\frac{1}{3}x + \\frac{1}{30}");
+        // 30 (x/3 + 1/30)^3 - 10/9 x^3 has no rounding residue left in its coefficients
+        let cube = &(&p * &p) * &p;
+        let x3 = Polynomial::from_terms(vec![Term::constant(Rational::new(10, 9))])
+            * Polynomial::<Rational>::parse_exact("x^3").unwrap();
+        let rest = cube.scale(Rational::from_integer(30)) - x3;
+        assert_eq!(rest.to_ascii(), "1/3*x^2 + 1/30*x + 1/900");
+        assert_eq!(
+            Polynomial::parse("0.1x").unwrap().to_exact().unwrap(),
+            Polynomial::<Rational>::parse_exact("x")
+                .unwrap()
+                .scale(Rational::new(1, 10))
+        );
+        // lowering to the graph rounds the coefficients to f32
+        let mut allocator = NodeAllocator::new();
+        let mut name_record = HashMap::new();
+        let graph = p.tranform_to_residual_graph(&mut name_record, &mut allocator);
+        let mut exec = Executor::new(graph, Optimizer::SGD(SGD::new(0.0)));
+        let mut s = Store::new();
+        exec.init_parameter(&HashMap::from([("x".to_string(), 1.5)]), &mut s)
+            .unwrap();
+        exec.init_contants(&mut s);
+        exec.forward(&mut s).unwrap();
+        let output = exec.get_output_value(&mut s).unwrap().value;
+        assert!((output - (0.5 + 1.0 / 30.0)).abs() < 1e-6);
+        assert_eq!(
+            p.to_float(),
+            Polynomial::parse("0.33333334x + 0.033333335").unwrap()
+        );
+    }
+    #[test]
+    fn display() {
+        let p = Polynomial::parse("7 - 2.5y + 3x^2 y").unwrap();
+        assert_eq!(p.to_string(), "3x\u{b2}y \u{2212} 2.5y + 7");
+        assert_eq!(format!("{:#}", p), "3x^2*y - 2.5y + 7");
+        assert_eq!(p.to_latex(), "3x^{2}y - 2.5y + 7");
+        let q = Polynomial::parse("-x_1^12 + e - rate*x_1").unwrap();
+        assert_eq!(
+            q.to_string(),
+            "\u{2212}x_1\u{b9}\u{b2} \u{2212} rate\u{b7}x_1 + e"
+        );
+        assert_eq!(
+            q.to_latex(),
+            "-x_{1}^{12} - \\mathrm{rate} \\cdot x_{1} + e"
+        );
+        assert_eq!(Polynomial::new().to_string(), "0");
+        assert_eq!(format!("{:#}", Polynomial::parse("2e").unwrap()), "2*e");
+        for text in ["3x^2*y - 2.5y + 7", "-x_1^12 - rate*x_1 + e", "2*e"] {
+            let poly = Polynomial::parse(text).unwrap();
+            assert_eq!(Polynomial::parse(&poly.to_ascii()).unwrap(), poly);
+        }
+    }
+    #[test]
+    fn parse_errors() {
+        let message = |text: &str| match Polynomial::parse(text) {
+            Err(FFError::Parse(info)) => info,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(
+            message("x^ + 1"),
+            "column 4: expected a non-negative integer exponent, found '+'"
+        );
+        assert_eq!(
+            message("x + * y"),
+            "column 5: expected a number or a variable, found '*'"
+        );
+        assert_eq!(
+            message("x = 1 = 2"),
+            "column 7: expected an operator, found '='"
+        );
+        assert_eq!(
+            message("2 x -"),
+            "column 6: expected a number or a variable, found end of input"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use crate::error::{FFError, Result};
+
+// SplitMix64, small and seedable, so runs are reproducible without external crates
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { state: seed }
+    }
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        return z ^ (z >> 31);
+    }
+    // uniform in [0, 1) from the top 53 bits
+    pub fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    }
+    pub fn uniform(&mut self, lower: f64, upper: f64) -> f64 {
+        return lower + (upper - lower) * self.next_f64();
+    }
+    // uniform in 0..n
+    pub fn below(&mut self, n: usize) -> usize {
+        return (self.next_f64() * n as f64) as usize % n.max(1);
+    }
+    // Fisher-Yates
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+// how a multi-start run spreads its initial points over the search box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    // independent uniform points
+    Uniform,
+    // one point in each of n slabs per coordinate
+    LatinHypercube,
+    // low-discrepancy Sobol sequence with a random digital shift
+    Sobol,
+}
+
+// n points in the unit cube [0, 1)^dim
+pub fn sample(sampling: Sampling, n: usize, dim: usize, rng: &mut Rng) -> Result<Vec<Vec<f64>>> {
+    return match sampling {
+        Sampling::Uniform => Ok((0..n)
+            .map(|_| (0..dim).map(|_| rng.next_f64()).collect())
+            .collect()),
+        Sampling::LatinHypercube => {
+            let mut points = vec![vec![0.0; dim]; n];
+            for d in 0..dim {
+                let mut strata = (0..n).collect::<Vec<usize>>();
+                rng.shuffle(&mut strata);
+                for (point, stratum) in points.iter_mut().zip(strata.iter()) {
+                    point[d] = (*stratum as f64 + rng.next_f64()) / n as f64;
+                }
+            }
+            Ok(points)
+        }
+        Sampling::Sobol => sobol(n, dim, rng),
+    };
+}
+
+// primitive polynomial degree s, its inner coefficients a and the initial direction
+// numbers m of Joe and Kuo, for dimensions 2 and up
+const SOBOL_DIRECTIONS: [(u32, u32, &[u32]); 15] = [
+    (1, 0, &[1]),
+    (2, 1, &[1, 3]),
+    (3, 1, &[1, 3, 1]),
+    (3, 2, &[1, 1, 1]),
+    (4, 1, &[1, 1, 3, 3]),
+    (4, 4, &[1, 3, 5, 13]),
+    (5, 2, &[1, 1, 5, 5, 17]),
+    (5, 4, &[1, 1, 5, 5, 5]),
+    (5, 7, &[1, 1, 7, 11, 19]),
+    (5, 11, &[1, 1, 5, 1, 1]),
+    (5, 13, &[1, 1, 1, 3, 11]),
+    (5, 14, &[1, 3, 5, 5, 31]),
+    (6, 1, &[1, 3, 3, 9, 7, 49]),
+    (6, 13, &[1, 1, 1, 15, 21, 21]),
+    (6, 16, &[1, 3, 1, 13, 27, 49]),
+];
+
+// 32 direction numbers per dimension, scaled to 32-bit fractions
+fn sobol_directions(d: usize) -> [u32; 32] {
+    let mut v = [0u32; 32];
+    if d == 0 {
+        for (i, x) in v.iter_mut().enumerate() {
+            *x = 1 << (31 - i);
+        }
+        return v;
+    }
+    let (s, a, m) = SOBOL_DIRECTIONS[d - 1];
+    let s = s as usize;
+    for i in 0..s {
+        v[i] = m[i] << (31 - i);
+    }
+    for i in s..32 {
+        v[i] = v[i - s] ^ (v[i - s] >> s);
+        for k in 1..s {
+            if (a >> (s - 1 - k)) & 1 == 1 {
+                v[i] ^= v[i - k];
+            }
+        }
+    }
+    return v;
+}
+
+// Gray-code ordered Sobol points, each coordinate xor-ed with a random shift
+fn sobol(n: usize, dim: usize, rng: &mut Rng) -> Result<Vec<Vec<f64>>> {
+    if dim > SOBOL_DIRECTIONS.len() + 1 {
+        return Err(FFError::Op(format!(
+            "sobol sampling supports up to {} dimensions, got {}",
+            SOBOL_DIRECTIONS.len() + 1,
+            dim
+        )));
+    }
+    let directions = (0..dim).map(sobol_directions).collect::<Vec<[u32; 32]>>();
+    let shift = (0..dim)
+        .map(|_| (rng.next_u64() >> 32) as u32)
+        .collect::<Vec<u32>>();
+    let mut x = vec![0u32; dim];
+    let mut points = Vec::with_capacity(n);
+    for i in 0..n {
+        points.push(
+            x.iter()
+                .zip(shift.iter())
+                .map(|(x, s)| (x ^ s) as f64 / 4294967296.0)
+                .collect(),
+        );
+        // the next point flips the direction of the lowest zero bit of i
+        let c = (!i).trailing_zeros() as usize;
+        for (x, v) in x.iter_mut().zip(directions.iter()) {
+            *x ^= v[c.min(31)];
+        }
+    }
+    return Ok(points);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn samplers() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        assert_eq!(a.next_u64(), b.next_u64());
+        let points = sample(Sampling::LatinHypercube, 8, 3, &mut a).unwrap();
+        for d in 0..3 {
+            let mut strata = points
+                .iter()
+                .map(|p| (p[d] * 8.0) as usize)
+                .collect::<Vec<usize>>();
+            strata.sort();
+            assert_eq!(strata, (0..8).collect::<Vec<usize>>());
+        }
+        // the first 16 points are a (0, 4, 2)-net, shifted or not: every 1/4 x 1/4 box
+        // gets exactly one of them
+        let points = sample(Sampling::Sobol, 16, 2, &mut Rng::new(1)).unwrap();
+        let mut boxes = points
+            .iter()
+            .map(|p| ((p[0] * 4.0) as usize, (p[1] * 4.0) as usize))
+            .collect::<Vec<(usize, usize)>>();
+        boxes.sort();
+        boxes.dedup();
+        assert_eq!(boxes.len(), 16);
+        assert!(points.iter().flatten().all(|x| (0.0..1.0).contains(x)));
+        assert!(sample(Sampling::Sobol, 4, 40, &mut a).is_err());
+    }
+}
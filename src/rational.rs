@@ -0,0 +1,250 @@
+use crate::bigint::BigInt;
+use crate::error::{FFError, Result};
+use std::cmp::Ordering;
+use std::fmt::{self, Display};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+// decimal exponents beyond this would only build huge powers of ten, far past any f64
+const MAX_EXPONENT: u32 = 1000;
+
+// exact fraction num / den in lowest terms with den > 0
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: BigInt,
+    den: BigInt,
+}
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Self {
+        return Rational::from_bigint(BigInt::from(num), BigInt::from(den));
+    }
+    pub fn from_bigint(num: BigInt, den: BigInt) -> Self {
+        assert!(!den.is_zero(), "rational with zero denominator");
+        let g = num.gcd(&den);
+        let (mut num, mut den) = if g.is_one() || g.is_zero() {
+            (num, den)
+        } else {
+            (num.div_rem(&g).0, den.div_rem(&g).0)
+        };
+        if den.is_negative() {
+            num = -num;
+            den = -den;
+        }
+        if num.is_zero() {
+            den = BigInt::one();
+        }
+        return Rational { num, den };
+    }
+    pub fn from_integer(n: i128) -> Self {
+        return Rational {
+            num: BigInt::from(n),
+            den: BigInt::one(),
+        };
+    }
+    pub fn zero() -> Self {
+        return Rational::from_integer(0);
+    }
+    pub fn one() -> Self {
+        return Rational::from_integer(1);
+    }
+    // the shortest decimal that rounds to the f32, so 0.1f32 becomes 1/10
+    pub fn from_f32(value: f32) -> Result<Self> {
+        if !value.is_finite() {
+            return Err(FFError::NonFinite(format!("{} is not a rational", value)));
+        }
+        return Rational::parse(&value.to_string());
+    }
+    // integers, decimals and fractions like "-3", "0.25", "1e-3" or "1/3"
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if let Some((num, den)) = text.split_once('/') {
+            if den.contains('/') {
+                return Err(FFError::Parse(format!("more than one / in {}", text)));
+            }
+            let (num, den) = (Rational::parse(num)?, Rational::parse(den)?);
+            if den.is_zero() {
+                return Err(FFError::Parse(format!("zero denominator in {}", text)));
+            }
+            return Ok(num / den);
+        }
+        if let Some((mantissa, exponent)) = text.split_once(['e', 'E']) {
+            let exponent = exponent.parse::<i32>()?;
+            if exponent.unsigned_abs() > MAX_EXPONENT {
+                return Err(FFError::Parse(format!(
+                    "exponent of {} is beyond {}",
+                    text, MAX_EXPONENT
+                )));
+            }
+            let power =
+                Rational::from_bigint(BigInt::from(10).pow(exponent.unsigned_abs()), BigInt::one());
+            let mantissa = Rational::parse(mantissa)?;
+            return Ok(match exponent < 0 {
+                true => mantissa / power,
+                false => mantissa * power,
+            });
+        }
+        let (integer, fraction) = text.split_once('.').unwrap_or((text, ""));
+        let num = BigInt::parse(&format!("{}{}", integer, fraction))?;
+        let den = BigInt::from(10).pow(fraction.len() as u32);
+        return Ok(Rational::from_bigint(num, den));
+    }
+    pub fn numerator(&self) -> &BigInt {
+        return &self.num;
+    }
+    pub fn denominator(&self) -> &BigInt {
+        return &self.den;
+    }
+    pub fn is_zero(&self) -> bool {
+        return self.num.is_zero();
+    }
+    pub fn is_one(&self) -> bool {
+        return self.num.is_one() && self.den.is_one();
+    }
+    pub fn is_negative(&self) -> bool {
+        return self.num.is_negative();
+    }
+    pub fn is_integer(&self) -> bool {
+        return self.den.is_one();
+    }
+    pub fn abs(&self) -> Rational {
+        return Rational {
+            num: self.num.abs(),
+            den: self.den.clone(),
+        };
+    }
+    pub fn recip(&self) -> Rational {
+        return Rational::from_bigint(self.den.clone(), self.num.clone());
+    }
+    pub fn to_f64(&self) -> f64 {
+        // scale the quotient to about 64 significant bits so huge numerators and
+        // denominators do not overflow on their own
+        let shift = self.num.bits() as i64 - self.den.bits() as i64 - 64;
+        let quotient = if shift > 0 {
+            self.num.div_rem(&self.den.shl(shift as usize)).0
+        } else {
+            self.num.shl((-shift) as usize).div_rem(&self.den).0
+        };
+        return quotient.to_f64() * 2f64.powi(shift.clamp(-2000, 2000) as i32);
+    }
+}
+
+impl Add<&Rational> for &Rational {
+    type Output = Rational;
+    fn add(self, rhs: &Rational) -> Rational {
+        if self.den == rhs.den {
+            return Rational::from_bigint(&self.num + &rhs.num, self.den.clone());
+        }
+        return Rational::from_bigint(
+            &(&self.num * &rhs.den) + &(&rhs.num * &self.den),
+            &self.den * &rhs.den,
+        );
+    }
+}
+impl Sub<&Rational> for &Rational {
+    type Output = Rational;
+    fn sub(self, rhs: &Rational) -> Rational {
+        return self + &(-rhs);
+    }
+}
+impl Mul<&Rational> for &Rational {
+    type Output = Rational;
+    fn mul(self, rhs: &Rational) -> Rational {
+        return Rational::from_bigint(&self.num * &rhs.num, &self.den * &rhs.den);
+    }
+}
+impl Div<&Rational> for &Rational {
+    type Output = Rational;
+    #[allow(clippy::suspicious_arithmetic_impl)]
+    fn div(self, rhs: &Rational) -> Rational {
+        return self * &rhs.recip();
+    }
+}
+impl Neg for &Rational {
+    type Output = Rational;
+    fn neg(self) -> Rational {
+        return Rational {
+            num: -&self.num,
+            den: self.den.clone(),
+        };
+    }
+}
+impl Add for Rational {
+    type Output = Rational;
+    fn add(self, rhs: Rational) -> Rational {
+        return &self + &rhs;
+    }
+}
+impl Sub for Rational {
+    type Output = Rational;
+    fn sub(self, rhs: Rational) -> Rational {
+        return &self - &rhs;
+    }
+}
+impl Mul for Rational {
+    type Output = Rational;
+    fn mul(self, rhs: Rational) -> Rational {
+        return &self * &rhs;
+    }
+}
+impl Div for Rational {
+    type Output = Rational;
+    fn div(self, rhs: Rational) -> Rational {
+        return &self / &rhs;
+    }
+}
+impl Neg for Rational {
+    type Output = Rational;
+    fn neg(self) -> Rational {
+        return -&self;
+    }
+}
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        return (&self.num * &other.den).cmp(&(&other.num * &self.den));
+    }
+}
+impl Display for Rational {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_integer() {
+            return write!(fmt, "{}", self.num);
+        }
+        return write!(fmt, "{}/{}", self.num, self.den);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn arithmetic() {
+        let third = Rational::new(1, 3);
+        let half = Rational::new(-2, -4);
+        assert_eq!(&third + &half, Rational::new(5, 6));
+        assert_eq!(&third - &half, Rational::new(-1, 6));
+        assert_eq!(&third * &half, Rational::new(1, 6));
+        assert_eq!(&third / &half, Rational::new(2, 3));
+        assert!(third < half && -&half < third);
+        assert_eq!(Rational::new(3, -6).to_string(), "-1/2");
+        assert_eq!(Rational::from_f32(0.1).unwrap(), Rational::new(1, 10));
+        assert_eq!(Rational::parse("-1.25").unwrap(), Rational::new(-5, 4));
+        assert_eq!(Rational::parse("2/6").unwrap(), Rational::new(1, 3));
+        assert_eq!(Rational::parse("2.5e-2").unwrap(), Rational::new(1, 40));
+        assert!(Rational::parse("1/0").is_err());
+        assert!(Rational::parse("1/2/3").is_err());
+        assert!(Rational::parse("1e999999999").is_err());
+        assert!(Rational::parse("1e-1001").is_err());
+    }
+    #[test]
+    fn no_overflow() {
+        // (1/3)^100 is far outside i128 but stays exact
+        let third = Rational::new(1, 3);
+        let power = (0..100).fold(Rational::one(), |acc, _| &acc * &third);
+        assert_eq!(power.denominator(), &BigInt::from(3).pow(100));
+        assert!((power.to_f64() / 3f64.powi(-100) - 1.0).abs() < 1e-12);
+        assert!((Rational::new(2, 3).to_f64() - 2.0 / 3.0).abs() < 1e-16);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::coefficient::Coefficient;
+use crate::deflation::Deflation;
+use crate::division::cut;
+use crate::error::{FFError, Result};
+use crate::polynomial::{horner, Polynomial, Term};
+use std::collections::HashMap;
+
+// substituted univariate coefficients below this (relative) are rounding noise
+const SUBSTITUTION_TOLERANCE: f64 = 1e-9;
+// a root of one equation solves the other when its residual is below this, relative
+// to the size of the terms
+const COMMON_ROOT_TOLERANCE: f64 = 1e-6;
+
+// (m + n) x (m + n) Sylvester matrix of p (degree m in `name`) and q (degree n), its
+// entries are polynomials in the other variables: n shifted rows of p's coefficients,
+// then m shifted rows of q's, highest degree first
+pub fn sylvester_matrix<C: Coefficient>(
+    p: &Polynomial<C>,
+    q: &Polynomial<C>,
+    name: &str,
+) -> Vec<Vec<Polynomial<C>>> {
+    let (a, b) = (p.coefficients_in(name), q.coefficients_in(name));
+    let (m, n) = (a.len() - 1, b.len() - 1);
+    let mut matrix = vec![vec![Polynomial::zero(); m + n]; m + n];
+    for i in 0..n {
+        for (j, c) in a.iter().rev().enumerate() {
+            matrix[i][i + j] = c.clone();
+        }
+    }
+    for i in 0..m {
+        for (j, c) in b.iter().rev().enumerate() {
+            matrix[n + i][i + j] = c.clone();
+        }
+    }
+    return matrix;
+}
+
+// determinant by the Samuelson-Berkowitz algorithm, which never divides and so works
+// for polynomial entries. vect holds the characteristic polynomial of the leading
+// r x r block, highest coefficient first, and grows one row and column at a time
+pub fn determinant<C: Coefficient>(matrix: &[Vec<Polynomial<C>>]) -> Polynomial<C> {
+    let n = matrix.len();
+    let one = Polynomial::from_terms(vec![Term::constant(C::one())]);
+    if n == 0 {
+        return one;
+    }
+    let mut vect = vec![one.clone(), -&matrix[0][0]];
+    for r in 1..n {
+        // first column of the Toeplitz matrix: 1, -a_rr, -R C, -R A C, ..., -R A^(r-1) C
+        // with A the leading block, R the row and C the column that extend it
+        let mut column = vec![one.clone(), -&matrix[r][r]];
+        let mut power = (0..r)
+            .map(|i| matrix[i][r].clone())
+            .collect::<Vec<Polynomial<C>>>();
+        for k in 0..r {
+            let product = (0..r).fold(Polynomial::zero(), |acc, j| {
+                &acc + &(&matrix[r][j] * &power[j])
+            });
+            column.push(-&product);
+            if k + 1 < r {
+                power = (0..r)
+                    .map(|i| {
+                        (0..r).fold(Polynomial::zero(), |acc, j| {
+                            &acc + &(&matrix[i][j] * &power[j])
+                        })
+                    })
+                    .collect();
+            }
+        }
+        vect = (0..r + 2)
+            .map(|i| {
+                (0..=i.min(r)).fold(Polynomial::zero(), |acc, j| {
+                    &acc + &(&column[i - j] * &vect[j])
+                })
+            })
+            .collect();
+    }
+    let det = vect.pop().unwrap();
+    return if n.is_multiple_of(2) { det } else { -&det };
+}
+
+// the resultant eliminating `name`: a polynomial in the other variables that vanishes
+// wherever p and q have a common root in `name`
+pub fn resultant<C: Coefficient>(
+    p: &Polynomial<C>,
+    q: &Polynomial<C>,
+    name: &str,
+) -> Polynomial<C> {
+    return determinant(&sylvester_matrix(p, q, name));
+}
+
+// real values of `name` where both p and q vanish once every other variable is fixed
+// by `values`
+pub fn back_substitute<C: Coefficient>(
+    p: &Polynomial<C>,
+    q: &Polynomial<C>,
+    name: &str,
+    values: &HashMap<String, f64>,
+) -> Result<Vec<f64>> {
+    let mut equations = Vec::new();
+    for poly in [p, q] {
+        let c = poly
+            .coefficients_in(name)
+            .iter()
+            .map(|c| c.evaluate(values))
+            .collect::<Result<Vec<f64>>>()?;
+        let scale = c.iter().fold(0.0, |m: f64, v| m.max(v.abs()));
+        equations.push(cut(&c, SUBSTITUTION_TOLERANCE * scale));
+    }
+    // the lowest degree equation that still involves `name`
+    let Some(lowest) = equations
+        .iter()
+        .filter(|c| c.len() > 1)
+        .min_by_key(|c| c.len())
+    else {
+        if equations.iter().all(|c| c[0] == 0.0) {
+            return Err(FFError::Polynomial(format!(
+                "{} is not determined, both equations vanish at {:?}",
+                name, values
+            )));
+        }
+        return Ok(Vec::new());
+    };
+    let mut roots = Vec::new();
+    for root in Deflation::new().enumerate(lowest, None)?.iter() {
+        let common = equations.iter().all(|c| {
+            let magnitude = c
+                .iter()
+                .rev()
+                .fold(0.0, |acc, a| acc * root.value.abs() + a.abs());
+            horner(c, root.value).abs() <= COMMON_ROOT_TOLERANCE * magnitude.max(1.0)
+        });
+        if common {
+            roots.push(root.value);
+        }
+    }
+    return Ok(roots);
+}
+
+// real solutions of two equations in two variables: the resultant eliminating `name`
+// is solved in the remaining variable, then each root is substituted back
+pub fn solve_by_elimination<C: Coefficient>(
+    p: &Polynomial<C>,
+    q: &Polynomial<C>,
+    name: &str,
+) -> Result<Vec<HashMap<String, f64>>> {
+    let r = resultant(p, q, name);
+    if r.is_zero() {
+        return Err(FFError::Polynomial(format!(
+            "the resultant in {} vanishes, the equations share a common factor",
+            name
+        )));
+    }
+    let variables = r.variables();
+    let other = match variables.as_slice() {
+        [] => return Ok(Vec::new()),
+        [other] => other.clone(),
+        _ => {
+            return Err(FFError::Polynomial(format!(
+                "eliminating {} leaves variables {:?}, expected one",
+                name, variables
+            )))
+        }
+    };
+    let mut solutions = Vec::new();
+    for root in r.real_roots_of(&other)?.iter() {
+        let values = HashMap::from([(other.clone(), *root)]);
+        for x in back_substitute(p, q, name, &values)? {
+            let mut solution = values.clone();
+            solution.insert(name.to_string(), x);
+            solutions.push(solution);
+        }
+    }
+    return Ok(solutions);
+}
+
+impl<C: Coefficient> Polynomial<C> {
+    // coefficients in `name` as polynomials in the other variables, lowest degree first
+    pub fn coefficients_in(&self, name: &str) -> Vec<Polynomial<C>> {
+        let mut coefficients = vec![Polynomial::zero(); self.degree(name) + 1];
+        for t in self
+            .get_terms()
+            .iter()
+            .filter(|t| !t.get_coefficient().is_zero())
+        {
+            let mut rest = Term::constant(t.get_coefficient());
+            for (other, order) in t.get_variables().iter() {
+                if other != name {
+                    rest.add_variable(other, *order);
+                }
+            }
+            coefficients[t.degree(name)].add_term(rest);
+        }
+        return coefficients;
+    }
+    pub fn resultant(&self, other: &Polynomial<C>, name: &str) -> Polynomial<C> {
+        return resultant(self, other, name);
+    }
+    // distinct real roots of a polynomial in `name` only, computed in f64
+    fn real_roots_of(&self, name: &str) -> Result<Vec<f64>> {
+        let c = self.univariate_coefficients(name)?;
+        let scale = c.iter().fold(0.0, |m: f64, v| m.max(v.abs()));
+        let roots = Deflation::new().enumerate(&cut(&c, SUBSTITUTION_TOLERANCE * scale), None)?;
+        return Ok(roots.iter().map(|r| r.value).collect());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rational::Rational;
+    #[test]
+    fn sylvester_resultant() {
+        // x^2 + y^2 - 5 and x y - 2 meet where y^4 - 5y^2 + 4 = 0
+        let p = Polynomial::<Rational>::parse_exact("x^2 + y^2 - 5").unwrap();
+        let q = Polynomial::<Rational>::parse_exact("x y - 2").unwrap();
+        assert_eq!(sylvester_matrix(&p, &q, "x").len(), 3);
+        assert_eq!(
+            p.resultant(&q, "x"),
+            Polynomial::<Rational>::parse_exact("y^4 - 5y^2 + 4").unwrap()
+        );
+        // a common root makes the resultant vanish
+        let a = Polynomial::parse("x^2 - 3x + 2").unwrap();
+        let b = Polynomial::parse("x^2 - 1").unwrap();
+        assert!(a.resultant(&b, "x").is_zero());
+        let c = Polynomial::parse("x + 3").unwrap();
+        assert_eq!(a.resultant(&c, "x"), Polynomial::constant(20.0));
+    }
+    #[test]
+    fn elimination() {
+        let p = Polynomial::parse("x^2 + y^2 - 5").unwrap();
+        let q = Polynomial::parse("x y - 2").unwrap();
+        let mut solutions =
+            solve_by_elimination(&p.to_exact().unwrap(), &q.to_exact().unwrap(), "x")
+                .unwrap()
+                .iter()
+                .map(|s| (s["x"], s["y"]))
+                .collect::<Vec<(f64, f64)>>();
+        solutions.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let expected = [(-2.0, -1.0), (-1.0, -2.0), (1.0, 2.0), (2.0, 1.0)];
+        assert_eq!(solutions.len(), expected.len());
+        for ((x, y), (ex, ey)) in solutions.iter().zip(expected.iter()) {
+            assert!((x - ex).abs() < 1e-9 && (y - ey).abs() < 1e-9);
+        }
+        // x + y = 3, x - y = 1 from the example system
+        let p = Polynomial::parse("x + y - 3").unwrap();
+        let q = Polynomial::parse("x - y - 1").unwrap();
+        let solutions = solve_by_elimination(&p, &q, "y").unwrap();
+        assert_eq!(solutions.len(), 1);
+        assert!((solutions[0]["x"] - 2.0).abs() < 1e-9 && (solutions[0]["y"] - 1.0).abs() < 1e-9);
+        assert!(solve_by_elimination(&p, &p.scale(2.0), "x").is_err());
+    }
+}
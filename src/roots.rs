@@ -0,0 +1,351 @@
+use crate::complex::Complex;
+use crate::error::{FFError, Result};
+use crate::linalg::{self, Matrix};
+use crate::polynomial::Polynomial;
+use std::f64::consts::PI;
+
+// default relative distance below which approximations count as one multiple root
+const CLUSTER_TOLERANCE: f64 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComplexRoot {
+    pub value: Complex,
+    // number of computed roots merged into this one
+    pub multiplicity: usize,
+    // |p(value)|
+    pub residual: f64,
+    // estimated radius of a disk around `value` holding `multiplicity` roots,
+    // a rigorous inclusion radius for simple roots
+    pub error_bound: f64,
+}
+impl ComplexRoot {
+    // the imaginary part is within the error bound (or rounding) of zero
+    pub fn is_real(&self) -> bool {
+        let rounding = 1e-12 * (1.0 + self.value.norm());
+        return self.value.im.abs() <= self.error_bound.max(rounding);
+    }
+}
+
+// Aberth-Ehrlich simultaneous iteration for all roots of a univariate polynomial
+pub struct Aberth {
+    max_iterations: usize,
+    tolerance: f64,
+    cluster_tolerance: f64,
+}
+impl Aberth {
+    pub fn new() -> Self {
+        Aberth {
+            max_iterations: 1000,
+            tolerance: 1e-14,
+            cluster_tolerance: CLUSTER_TOLERANCE,
+        }
+    }
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        return self;
+    }
+    // a root stops moving once its correction is below `tolerance` relative to its size
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        return self;
+    }
+    // approximations closer than `cluster_tolerance` relative to their size are merged
+    // even when their inclusion disks do not overlap, multiple roots only converge to
+    // about eps^(1/multiplicity)
+    pub fn with_cluster_tolerance(mut self, cluster_tolerance: f64) -> Self {
+        self.cluster_tolerance = cluster_tolerance;
+        return self;
+    }
+    // coefficients lowest degree first
+    pub fn solve(&self, coefficients: &[f64]) -> Result<Vec<ComplexRoot>> {
+        return solve_with(
+            coefficients,
+            self.cluster_tolerance,
+            |c| Ok(self.iterate(c)),
+        );
+    }
+    fn iterate(&self, c: &[f64]) -> Vec<Complex> {
+        let n = c.len() - 1;
+        // start on a circle whose radius is the geometric mean of the root moduli
+        let radius = (c[0].abs() / c[n].abs()).powf(1.0 / n as f64);
+        let z = (0..n)
+            .map(|k| Complex::from_polar(radius, 2.0 * PI * k as f64 / n as f64 + 0.4))
+            .collect::<Vec<Complex>>();
+        return self.iterate_from(c, z);
+    }
+    // Aberth steps from the approximations `z`, one per root
+    fn iterate_from(&self, c: &[f64], mut z: Vec<Complex>) -> Vec<Complex> {
+        let n = c.len() - 1;
+        let mut done = vec![false; n];
+        for _ in 0..self.max_iterations {
+            for k in 0..n {
+                if done[k] {
+                    continue;
+                }
+                let (p, dp) = horner_with_derivative(c, z[k]);
+                if p.norm() == 0.0 {
+                    done[k] = true;
+                    continue;
+                }
+                let ratio = p / dp;
+                let repulsion = (0..n)
+                    .filter(|j| *j != k)
+                    .fold(Complex::zero(), |acc, j| acc + (z[k] - z[j]).inv());
+                let w = ratio / (Complex::one() - ratio * repulsion);
+                if !w.is_finite() {
+                    done[k] = true;
+                    continue;
+                }
+                z[k] -= w;
+                done[k] = w.norm() <= self.tolerance * z[k].norm().max(f64::MIN_POSITIVE);
+            }
+            if done.iter().all(|d| *d) {
+                break;
+            }
+        }
+        return z;
+    }
+}
+impl Default for Aberth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// companion matrix of a polynomial with coefficients lowest degree first, upper
+// Hessenberg with the normalized coefficients in the first row
+pub fn companion_matrix(coefficients: &[f64]) -> Result<Matrix> {
+    let last = coefficients
+        .iter()
+        .rposition(|c| *c != 0.0)
+        .ok_or(FFError::Polynomial(
+            "the zero polynomial has no companion matrix".to_string(),
+        ))?;
+    let mut m = Matrix::new(last, last);
+    for j in 0..last {
+        m[(0, j)] = -coefficients[last - 1 - j] / coefficients[last];
+    }
+    for i in 1..last {
+        m[(i, i - 1)] = 1.0;
+    }
+    return Ok(m);
+}
+
+// all complex roots from the eigenvalues of the companion matrix. the eigenvalues of a
+// multiple root spread out as a ring wider than their inclusion disks, so they are
+// polished by Aberth steps before being clustered like `Aberth::solve`
+pub fn companion_roots(coefficients: &[f64]) -> Result<Vec<ComplexRoot>> {
+    return solve_with(coefficients, CLUSTER_TOLERANCE, |c| {
+        let z = linalg::eigenvalues(&companion_matrix(c)?)?;
+        return Ok(Aberth::new().iterate_from(c, z));
+    });
+}
+
+impl Polynomial {
+    // all complex roots of a univariate polynomial with the default `Aberth` settings
+    pub fn complex_roots(&self) -> Result<Vec<ComplexRoot>> {
+        return Aberth::new().solve(&self.univariate()?);
+    }
+    // all complex roots of a univariate polynomial from its companion matrix
+    pub fn companion_roots(&self) -> Result<Vec<ComplexRoot>> {
+        return companion_roots(&self.univariate()?);
+    }
+}
+
+// splits off roots at zero, finds the others with `approximate` on the remaining
+// coefficients and clusters them, sorted by real then imaginary part
+fn solve_with<F>(
+    coefficients: &[f64],
+    cluster_tolerance: f64,
+    approximate: F,
+) -> Result<Vec<ComplexRoot>>
+where
+    F: FnOnce(&[f64]) -> Result<Vec<Complex>>,
+{
+    let last = coefficients
+        .iter()
+        .rposition(|c| *c != 0.0)
+        .ok_or(FFError::Polynomial(
+            "the zero polynomial has no isolated roots".to_string(),
+        ))?;
+    // x^k factors give exact roots at zero
+    let zeros = coefficients.iter().position(|c| *c != 0.0).unwrap();
+    let c = &coefficients[zeros..=last];
+    let mut roots = Vec::new();
+    if zeros > 0 {
+        roots.push(ComplexRoot {
+            value: Complex::zero(),
+            multiplicity: zeros,
+            residual: 0.0,
+            error_bound: 0.0,
+        });
+    }
+    if c.len() == 1 {
+        return Ok(roots);
+    }
+    let z = approximate(c)?;
+    roots.extend(cluster(c, &z, cluster_tolerance));
+    roots.sort_by(|a, b| {
+        a.value
+            .re
+            .total_cmp(&b.value.re)
+            .then(a.value.im.total_cmp(&b.value.im))
+    });
+    return Ok(roots);
+}
+
+// (p(z), p'(z)) for coefficients lowest degree first
+pub fn horner_with_derivative(c: &[f64], z: Complex) -> (Complex, Complex) {
+    let mut p = Complex::zero();
+    let mut dp = Complex::zero();
+    for coefficient in c.iter().rev() {
+        dp = dp * z + p;
+        p = p * z + *coefficient;
+    }
+    return (p, dp);
+}
+
+// n |p(z)| / |c_n prod (z - others)|, a disk of this radius around z holds a root
+fn inclusion_radius(c: &[f64], z: Complex, others: &[Complex], power: usize) -> f64 {
+    let n = c.len() - 1;
+    let (p, _) = horner_with_derivative(c, z);
+    let product = others
+        .iter()
+        .fold(Complex::from_real(c[n]), |acc, o| acc * (z - *o));
+    return (n as f64 * p.norm() / product.norm()).powf(1.0 / power as f64);
+}
+
+// merges approximations whose inclusion disks overlap (or that lie within `tolerance`
+// of each other) into roots with multiplicity
+fn cluster(c: &[f64], z: &[Complex], tolerance: f64) -> Vec<ComplexRoot> {
+    let n = z.len();
+    let radii = (0..n)
+        .map(|k| {
+            let others = (0..n)
+                .filter(|j| *j != k)
+                .map(|j| z[j])
+                .collect::<Vec<Complex>>();
+            inclusion_radius(c, z[k], &others, 1)
+        })
+        .collect::<Vec<f64>>();
+    // union-find over overlapping disks
+    let mut parent = (0..n).collect::<Vec<usize>>();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+        if parent[i] != i {
+            parent[i] = find(parent, parent[i]);
+        }
+        return parent[i];
+    }
+    for i in 0..n {
+        for j in i + 1..n {
+            let distance = (z[i] - z[j]).norm();
+            let scale = 1.0 + z[i].norm().max(z[j].norm());
+            if distance <= radii[i] + radii[j] || distance <= tolerance * scale {
+                let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+                parent[a] = b;
+            }
+        }
+    }
+    let mut roots = Vec::new();
+    for representative in 0..n {
+        let members = (0..n)
+            .filter(|k| find(&mut parent, *k) == representative)
+            .collect::<Vec<usize>>();
+        if members.is_empty() {
+            continue;
+        }
+        let m = members.len();
+        let centroid = members.iter().fold(Complex::zero(), |acc, k| acc + z[*k]) / m as f64;
+        let others = (0..n)
+            .filter(|k| !members.contains(k))
+            .map(|k| z[k])
+            .collect::<Vec<Complex>>();
+        let (p, _) = horner_with_derivative(c, centroid);
+        roots.push(ComplexRoot {
+            value: centroid,
+            multiplicity: m,
+            residual: p.norm(),
+            error_bound: inclusion_radius(c, centroid, &others, m),
+        });
+    }
+    return roots;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    fn assert_root(roots: &[ComplexRoot], value: Complex, multiplicity: usize) -> ComplexRoot {
+        let root = roots
+            .iter()
+            .find(|r| (r.value - value).norm() < 1e-6)
+            .unwrap_or_else(|| panic!("{} not found in {:?}", value, roots));
+        assert_eq!(root.multiplicity, multiplicity);
+        assert!((root.value - value).norm() <= root.error_bound.max(1e-12));
+        return *root;
+    }
+    #[test]
+    fn simple_roots() {
+        // (x - 1)(x + 2)(x^2 + 1)
+        let p = Polynomial::parse("x^4 + x^3 - x^2 + x - 2").unwrap();
+        let roots = p.complex_roots().unwrap();
+        assert_eq!(roots.len(), 4);
+        assert!(assert_root(&roots, Complex::from_real(-2.0), 1).is_real());
+        assert!(!assert_root(&roots, Complex::new(0.0, -1.0), 1).is_real());
+        assert!(!assert_root(&roots, Complex::new(0.0, 1.0), 1).is_real());
+        assert!(assert_root(&roots, Complex::from_real(1.0), 1).is_real());
+        assert!(roots.iter().all(|r| r.residual < 1e-10));
+    }
+    #[test]
+    fn multiple_roots() {
+        // x^2 (x - 1)^3 (x + 3)
+        let p = Polynomial::parse("x^6 - 6x^4 + 8x^3 - 3x^2").unwrap();
+        let roots = p.complex_roots().unwrap();
+        assert_eq!(roots.iter().map(|r| r.multiplicity).sum::<usize>(), 6);
+        assert_eq!(roots.len(), 3);
+        assert_root(&roots, Complex::from_real(-3.0), 1);
+        assert_root(&roots, Complex::zero(), 2);
+        assert_root(&roots, Complex::from_real(1.0), 3);
+    }
+    #[test]
+    fn companion() {
+        // (x - 1)(x + 2)(x^2 + 1) and x^2 (x - 1)^3 (x + 3) as in the Aberth tests
+        let p = Polynomial::parse("x^4 + x^3 - x^2 + x - 2").unwrap();
+        let roots = p.companion_roots().unwrap();
+        assert_eq!(roots.len(), 4);
+        for value in [
+            Complex::from_real(-2.0),
+            Complex::new(0.0, -1.0),
+            Complex::new(0.0, 1.0),
+            Complex::from_real(1.0),
+        ] {
+            assert_root(&roots, value, 1);
+        }
+        let p = Polynomial::parse("x^6 - 6x^4 + 8x^3 - 3x^2").unwrap();
+        let roots = p.companion_roots().unwrap();
+        assert_eq!(roots.len(), 3);
+        assert_root(&roots, Complex::from_real(-3.0), 1);
+        assert_root(&roots, Complex::zero(), 2);
+        assert_root(&roots, Complex::from_real(1.0), 3);
+        let aberth = p.complex_roots().unwrap();
+        for (a, b) in roots.iter().zip(aberth.iter()) {
+            assert!((a.value - b.value).norm() <= a.error_bound + b.error_bound + 1e-12);
+        }
+        // (x - 1)^5
+        let p = Polynomial::parse("x^5 - 5x^4 + 10x^3 - 10x^2 + 5x - 1").unwrap();
+        let roots = p.companion_roots().unwrap();
+        assert_eq!(roots.len(), 1, "{:?}", roots);
+        assert_eq!(roots[0].multiplicity, 5);
+        // a five-fold root is only determined to about eps^(1/5)
+        assert!((roots[0].value - Complex::from_real(1.0)).norm() <= roots[0].error_bound);
+    }
+    #[test]
+    fn degenerate() {
+        assert!(Polynomial::parse("3")
+            .unwrap()
+            .complex_roots()
+            .unwrap()
+            .is_empty());
+        assert!(Polynomial::new().complex_roots().is_err());
+        assert!(Polynomial::parse("x y").unwrap().complex_roots().is_err());
+    }
+}
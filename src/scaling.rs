@@ -0,0 +1,184 @@
+use crate::error::{FFError, Result};
+use crate::polynomial::Polynomial;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+// scales below this fall back to 1, the equation is already (nearly) solved
+const MIN_SCALE: f64 = 1e-12;
+
+// how each residual is normalized before it enters the loss
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scaling {
+    // residuals as they are
+    Unscaled,
+    // divided by the euclidean norm of the equation's coefficients
+    CoefficientNorm,
+    // divided by |residual| at the initial point, every equation starts at 1
+    InitialResidual,
+}
+
+// residual i enters the loss as loss(factor_i * r_i) with factor_i = sqrt(weights[i]) /
+// scales[i], for the squared loss that is weights[i] * (r_i / scales[i])^2
+#[derive(Debug, Clone, PartialEq)]
+pub struct EquationScaling {
+    pub scaling: Scaling,
+    pub weights: Vec<f32>,
+    pub scales: Vec<f32>,
+}
+impl EquationScaling {
+    // `weights` has one entry per equation, or is empty for equal weights
+    pub fn new(
+        polys: &[Polynomial],
+        weights: &[f32],
+        scaling: Scaling,
+        init: &HashMap<String, f32>,
+    ) -> Result<Self> {
+        let weights = match weights.len() {
+            0 => vec![1.0; polys.len()],
+            n if n == polys.len() => weights.to_vec(),
+            n => {
+                return Err(FFError::Polynomial(format!(
+                    "{} weights for {} equations",
+                    n,
+                    polys.len()
+                )))
+            }
+        };
+        if let Some(w) = weights.iter().find(|w| !w.is_finite() || **w < 0.0) {
+            return Err(FFError::Polynomial(format!(
+                "equation weights must be finite and non-negative, got {}",
+                w
+            )));
+        }
+        let values = init
+            .iter()
+            .map(|(name, value)| (name.clone(), *value as f64))
+            .collect::<HashMap<String, f64>>();
+        let scales = polys
+            .iter()
+            .map(|p| {
+                let scale = match scaling {
+                    Scaling::Unscaled => 1.0,
+                    Scaling::CoefficientNorm => p
+                        .get_terms()
+                        .iter()
+                        .map(|t| (t.get_coefficient() as f64).powi(2))
+                        .sum::<f64>()
+                        .sqrt(),
+                    Scaling::InitialResidual => p.evaluate(&values)?.abs(),
+                };
+                return Ok(if scale > MIN_SCALE { scale as f32 } else { 1.0 });
+            })
+            .collect::<Result<Vec<f32>>>()?;
+        return Ok(EquationScaling {
+            scaling,
+            weights,
+            scales,
+        });
+    }
+    // sqrt(weight) / scale, what each residual is multiplied by before squaring
+    pub fn factors(&self) -> Vec<f32> {
+        return self
+            .weights
+            .iter()
+            .zip(self.scales.iter())
+            .map(|(w, s)| w.sqrt() / s)
+            .collect();
+    }
+    // the equations multiplied by their factors, for solvers that work on the residuals
+    // directly, whose sum of squares is then the weighted and scaled loss
+    pub fn apply(&self, polys: &[Polynomial]) -> Vec<Polynomial> {
+        return polys
+            .iter()
+            .zip(self.factors())
+            .map(|(p, factor)| p.scale(factor))
+            .collect();
+    }
+}
+impl Display for EquationScaling {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{:?}", self.scaling)?;
+        for (i, (w, s)) in self.weights.iter().zip(self.scales.iter()).enumerate() {
+            write!(fmt, "\n  equation {}: weight {}, scale {}", i + 1, w, s)?;
+        }
+        return Ok(());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::Executor;
+    use crate::graph::NodeAllocator;
+    use crate::least_squares::{LeastSquares, Method};
+    use crate::loss::Loss;
+    use crate::optimizer::{Optimizer, SGD};
+    use crate::polynomial::tranform_weighted_system_to_graph;
+    use crate::store::Store;
+    #[test]
+    fn weighted_loss() {
+        let polys = vec![
+            Polynomial::parse("3000x - 4000y").unwrap(),
+            Polynomial::parse("x + y - 1").unwrap(),
+        ];
+        let init = HashMap::from([("x".to_string(), 1.0), ("y".to_string(), 2.0)]);
+        let scaling = EquationScaling::new(&polys, &[], Scaling::CoefficientNorm, &init).unwrap();
+        assert_eq!(scaling.scales[0], 5000.0);
+        let scaling =
+            EquationScaling::new(&polys, &[4.0, 1.0], Scaling::InitialResidual, &init).unwrap();
+        assert_eq!(scaling.scales, vec![5000.0, 2.0]);
+        assert_eq!(scaling.factors(), vec![2.0 / 5000.0, 0.5]);
+        assert_eq!(scaling.apply(&polys)[1], polys[1].scale(0.5));
+        // each equation contributes its weight at the initial point
+        let mut allocator = NodeAllocator::new();
+        let mut name_record = HashMap::new();
+        let graph = tranform_weighted_system_to_graph(
+            &polys,
+            &scaling.factors(),
+            Loss::Squared,
+            &mut name_record,
+            &mut allocator,
+        )
+        .unwrap();
+        let mut exec = Executor::new(graph, Optimizer::SGD(SGD::new(0.0)));
+        let mut s = Store::new();
+        exec.init_parameter(&init, &mut s).unwrap();
+        exec.init_contants(&mut s);
+        exec.forward(&mut s).unwrap();
+        let loss = exec.get_output_value(&mut s).unwrap().value;
+        assert!((loss - 5.0).abs() < 1e-5);
+        assert!(EquationScaling::new(&polys, &[1.0], Scaling::Unscaled, &init).is_err());
+        assert!(EquationScaling::new(&polys, &[1.0, -1.0], Scaling::Unscaled, &init).is_err());
+    }
+    #[test]
+    fn zero_weight() {
+        // the last equation is dropped, its scaled polynomial has no terms
+        let polys = vec![
+            Polynomial::parse("x - 2").unwrap(),
+            Polynomial::parse("y - 1").unwrap(),
+            Polynomial::parse("x + y").unwrap(),
+        ];
+        let init = HashMap::from([("x".to_string(), 0.0), ("y".to_string(), 0.0)]);
+        let scaling =
+            EquationScaling::new(&polys, &[1.0, 1.0, 0.0], Scaling::Unscaled, &init).unwrap();
+        let res = LeastSquares::new(Method::LevenbergMarquardt)
+            .solve(&scaling.apply(&polys), &init)
+            .unwrap();
+        assert!(res.converged);
+        assert!((res.parameters["x"] - 2.0).abs() < 1e-5);
+        assert!((res.parameters["y"] - 1.0).abs() < 1e-5);
+        assert_eq!(res.residuals[2], 0.0);
+    }
+    #[test]
+    fn mismatched_factors() {
+        let polys = vec![Polynomial::parse("x - 1").unwrap(); 2];
+        assert!(tranform_weighted_system_to_graph(
+            &polys,
+            &[1.0],
+            Loss::Squared,
+            &mut HashMap::new(),
+            &mut NodeAllocator::new(),
+        )
+        .is_err());
+    }
+}
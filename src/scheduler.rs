@@ -0,0 +1,225 @@
+use crate::error::Result;
+use crate::optimizer::{Objective, Optimize, Optimizer, VectorOptimize};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    Constant,
+    // lr * gamma^(epoch / step_size)
+    StepDecay {
+        step_size: usize,
+        gamma: f32,
+    },
+    // lr * gamma^epoch
+    Exponential {
+        gamma: f32,
+    },
+    // half cosine from lr down to min_lr over `period` epochs, then stays at min_lr
+    CosineAnnealing {
+        period: usize,
+        min_lr: f32,
+    },
+    // ramps linearly from lr / steps up to lr, then follows `then` shifted by `steps`
+    LinearWarmup {
+        steps: usize,
+        then: Box<Schedule>,
+    },
+    // lr * factor once the loss has not improved by `threshold` (relative) for `patience` epochs
+    ReduceOnPlateau {
+        factor: f32,
+        patience: usize,
+        threshold: f32,
+        min_lr: f32,
+    },
+}
+
+// wraps an optimizer and rewrites its learning rate, advanced once per iteration with `step`
+pub struct Scheduler {
+    optimizer: Optimizer,
+    schedule: Schedule,
+    base_lr: f32,
+    epoch: usize,
+    // plateau state: best loss seen, epochs without improvement, accumulated reduction
+    best_loss: f32,
+    bad_epochs: usize,
+    plateau_scale: f32,
+}
+impl Scheduler {
+    pub fn new(optimizer: Optimizer, schedule: Schedule) -> Self {
+        let base_lr = optimizer.learning_rate().unwrap_or(0.0);
+        let mut scheduler = Scheduler {
+            optimizer,
+            schedule,
+            base_lr,
+            epoch: 0,
+            best_loss: f32::INFINITY,
+            bad_epochs: 0,
+            plateau_scale: 1.0,
+        };
+        scheduler.apply();
+        return scheduler;
+    }
+    pub fn get_optimizer(&self) -> &Optimizer {
+        return &self.optimizer;
+    }
+    pub fn get_epoch(&self) -> usize {
+        return self.epoch;
+    }
+    pub fn learning_rate(&self) -> f32 {
+        return self.optimizer.learning_rate().unwrap_or(self.base_lr);
+    }
+    pub fn step(&mut self, loss: f32) {
+        self.epoch += 1;
+        self.observe(loss);
+        self.apply();
+    }
+    fn apply(&mut self) {
+        let lr = scheduled_rate(&self.schedule, self.base_lr, self.epoch) * self.plateau_scale;
+        self.optimizer.set_learning_rate(lr);
+    }
+    fn observe(&mut self, loss: f32) {
+        let (factor, patience, threshold, min_lr) = match plateau(&self.schedule) {
+            Some(config) => config,
+            None => return,
+        };
+        // relative to |best| so that negative losses improve by going further down
+        if self.best_loss == f32::INFINITY
+            || loss < self.best_loss - threshold * self.best_loss.abs()
+        {
+            self.best_loss = loss;
+            self.bad_epochs = 0;
+            return;
+        }
+        self.bad_epochs += 1;
+        if self.bad_epochs > patience {
+            self.bad_epochs = 0;
+            let scale = self.plateau_scale * factor;
+            self.plateau_scale = scale.max(min_lr / self.base_lr.max(f32::MIN_POSITIVE));
+        }
+    }
+}
+impl Optimize for Scheduler {
+    fn register(&mut self, index: usize) {
+        Optimize::register(&mut self.optimizer, index);
+    }
+    fn optimize(&mut self, index: usize, old_value: f32, grad: f32) -> f32 {
+        return self.optimizer.optimize(index, old_value, grad);
+    }
+}
+impl VectorOptimize for Scheduler {
+    fn register(&mut self, indices: &[usize]) {
+        VectorOptimize::register(&mut self.optimizer, indices);
+    }
+    fn optimize_vector(
+        &mut self,
+        indices: &[usize],
+        values: &mut [f32],
+        loss: f32,
+        grads: &[f32],
+        objective: &mut Objective,
+    ) -> Result<()> {
+        return self
+            .optimizer
+            .optimize_vector(indices, values, loss, grads, objective);
+    }
+}
+
+fn plateau(schedule: &Schedule) -> Option<(f32, usize, f32, f32)> {
+    return match schedule {
+        Schedule::ReduceOnPlateau {
+            factor,
+            patience,
+            threshold,
+            min_lr,
+        } => Some((*factor, *patience, *threshold, *min_lr)),
+        Schedule::LinearWarmup { then, .. } => plateau(then),
+        _ => None,
+    };
+}
+
+// learning rate at `epoch` before any plateau reduction
+fn scheduled_rate(schedule: &Schedule, base_lr: f32, epoch: usize) -> f32 {
+    return match schedule {
+        Schedule::Constant | Schedule::ReduceOnPlateau { .. } => base_lr,
+        Schedule::StepDecay { step_size, gamma } => {
+            base_lr * gamma.powi((epoch / (*step_size).max(1)) as i32)
+        }
+        Schedule::Exponential { gamma } => base_lr * gamma.powi(epoch as i32),
+        Schedule::CosineAnnealing { period, min_lr } => {
+            let t = epoch.min(*period) as f32 / (*period).max(1) as f32;
+            min_lr + (base_lr - min_lr) * 0.5 * (1.0 + (std::f32::consts::PI * t).cos())
+        }
+        Schedule::LinearWarmup { steps, then } => {
+            if epoch < *steps {
+                scheduled_rate(then, base_lr, 0) * (epoch + 1) as f32 / *steps as f32
+            } else {
+                scheduled_rate(then, base_lr, epoch - steps)
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::optimizer::SGD;
+    fn rates(schedule: Schedule, losses: &[f32]) -> Vec<f32> {
+        let mut scheduler = Scheduler::new(Optimizer::SGD(SGD::new(1.0)), schedule);
+        let mut res = vec![scheduler.learning_rate()];
+        for loss in losses.iter() {
+            scheduler.step(*loss);
+            res.push(scheduler.learning_rate());
+        }
+        return res;
+    }
+    fn assert_close(a: &[f32], b: &[f32]) {
+        assert!(
+            a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-6),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+    #[test]
+    fn schedules() {
+        let step = Schedule::StepDecay {
+            step_size: 2,
+            gamma: 0.5,
+        };
+        assert_close(&rates(step, &[0.0; 4]), &[1.0, 1.0, 0.5, 0.5, 0.25]);
+        let exp = Schedule::Exponential { gamma: 0.5 };
+        assert_close(&rates(exp, &[0.0; 2]), &[1.0, 0.5, 0.25]);
+        let cosine = Schedule::CosineAnnealing {
+            period: 2,
+            min_lr: 0.0,
+        };
+        assert_close(&rates(cosine, &[0.0; 3]), &[1.0, 0.5, 0.0, 0.0]);
+        let warmup = Schedule::LinearWarmup {
+            steps: 4,
+            then: Box::new(Schedule::Constant),
+        };
+        assert_close(&rates(warmup, &[0.0; 4]), &[0.25, 0.5, 0.75, 1.0, 1.0]);
+    }
+    #[test]
+    fn reduce_on_plateau() {
+        let plateau = Schedule::ReduceOnPlateau {
+            factor: 0.5,
+            patience: 1,
+            threshold: 0.0,
+            min_lr: 0.2,
+        };
+        let losses = [4.0, 3.0, 3.0, 3.0, 2.0, 2.0, 2.0, 2.0, 2.0];
+        assert_close(
+            &rates(plateau, &losses),
+            &[1.0, 1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.25, 0.25, 0.2],
+        );
+        // a barrier loss can be negative, -2.5 improves on -2 by more than 10% and
+        // -2.6 on -2.5 does not
+        let plateau = Schedule::ReduceOnPlateau {
+            factor: 0.5,
+            patience: 0,
+            threshold: 0.1,
+            min_lr: 0.0,
+        };
+        assert_close(&rates(plateau, &[-2.0, -2.5, -2.6]), &[1.0, 1.0, 1.0, 0.5]);
+    }
+}
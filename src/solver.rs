@@ -0,0 +1,256 @@
+use crate::error::{FFError, Result};
+use crate::executor::Executor;
+use crate::polynomial::Polynomial;
+use crate::store::Store;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// why `Solver::solve` stopped
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Termination {
+    // loss at or below the loss tolerance, the system is solved
+    LossTolerance,
+    // gradient norm at or below its tolerance, a stationary point that need not be a root
+    GradientTolerance,
+    // the last step moved no parameter by more than the parameter tolerance
+    ParameterTolerance,
+    MaxIterations,
+    TimeBudget,
+    // the loss became NaN or infinite, usually a diverging step size
+    NonFinite,
+    // the optimizer's line search found no acceptable step, the parameters are those
+    // before the failed step
+    LineSearchFailed,
+}
+
+#[derive(Debug, Clone)]
+pub struct SolveReport {
+    pub termination: Termination,
+    // optimizer steps taken
+    pub iterations: usize,
+    pub parameters: HashMap<String, f32>,
+    // residual of every equation at the final parameters, evaluated in f64
+    pub residuals: Vec<f64>,
+    // loss before each step and at the final parameters
+    pub loss_history: Vec<f32>,
+}
+impl SolveReport {
+    pub fn final_loss(&self) -> f32 {
+        return self.loss_history.last().copied().unwrap_or(f32::NAN);
+    }
+}
+
+// forward, backward and optimizer steps on an initialized executor until a stopping
+// criterion holds, a tolerance of 0 disables its criterion
+#[derive(Debug, Clone)]
+pub struct Solver {
+    max_iterations: usize,
+    loss_tolerance: f32,
+    gradient_tolerance: f32,
+    parameter_tolerance: f32,
+    time_budget: Option<Duration>,
+}
+impl Solver {
+    pub fn new() -> Self {
+        Solver {
+            max_iterations: 200,
+            loss_tolerance: 1e-10,
+            gradient_tolerance: 0.0,
+            parameter_tolerance: 0.0,
+            time_budget: None,
+        }
+    }
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        return self;
+    }
+    pub fn with_loss_tolerance(mut self, loss_tolerance: f32) -> Self {
+        self.loss_tolerance = loss_tolerance;
+        return self;
+    }
+    // euclidean norm of the parameter gradient
+    pub fn with_gradient_tolerance(mut self, gradient_tolerance: f32) -> Self {
+        self.gradient_tolerance = gradient_tolerance;
+        return self;
+    }
+    // largest parameter change of a step, relative to max(1, |parameter|)
+    pub fn with_parameter_tolerance(mut self, parameter_tolerance: f32) -> Self {
+        self.parameter_tolerance = parameter_tolerance;
+        return self;
+    }
+    pub fn with_time_budget(mut self, time_budget: Duration) -> Self {
+        self.time_budget = Some(time_budget);
+        return self;
+    }
+    // `polys` are the equations behind the executor's graph, for the per-equation residuals
+    pub fn solve(
+        &self,
+        exec: &mut Executor,
+        s: &mut Store,
+        polys: &[Polynomial],
+    ) -> Result<SolveReport> {
+        let start = Instant::now();
+        let mut loss_history = Vec::new();
+        let mut iterations = 0;
+        let mut stalled = false;
+        let termination = loop {
+            exec.forward(s)?;
+            exec.backward(s)?;
+            let loss = exec.get_output_value(s)?.value;
+            loss_history.push(loss);
+            if !loss.is_finite() {
+                break Termination::NonFinite;
+            }
+            if loss <= self.loss_tolerance {
+                break Termination::LossTolerance;
+            }
+            if stalled {
+                break Termination::ParameterTolerance;
+            }
+            let gradient = parameters(exec, s)?
+                .values()
+                .map(|(_, grad)| grad * grad)
+                .sum::<f32>()
+                .sqrt();
+            if gradient <= self.gradient_tolerance {
+                break Termination::GradientTolerance;
+            }
+            if iterations >= self.max_iterations {
+                break Termination::MaxIterations;
+            }
+            if self
+                .time_budget
+                .is_some_and(|budget| start.elapsed() >= budget)
+            {
+                break Termination::TimeBudget;
+            }
+            let before = parameters(exec, s)?;
+            match exec.optimize(s) {
+                Err(FFError::LineSearch(_)) => break Termination::LineSearchFailed,
+                result => result?,
+            }
+            exec.clear_grad(s)?;
+            exec.step_scheduler(loss);
+            iterations += 1;
+            let change = parameters(exec, s)?
+                .iter()
+                .map(|(name, (value, _))| (value - before[name].0).abs() / value.abs().max(1.0))
+                .fold(0.0, f32::max);
+            stalled = self.parameter_tolerance > 0.0 && change <= self.parameter_tolerance;
+        };
+        exec.clear_grad(s)?;
+        let parameters = parameters(exec, s)?
+            .into_iter()
+            .map(|(name, (value, _))| (name, value))
+            .collect::<HashMap<String, f32>>();
+        let values = parameters
+            .iter()
+            .map(|(name, value)| (name.clone(), *value as f64))
+            .collect::<HashMap<String, f64>>();
+        let residuals = polys
+            .iter()
+            .map(|p| p.evaluate(&values))
+            .collect::<Result<Vec<f64>>>()?;
+        return Ok(SolveReport {
+            termination,
+            iterations,
+            parameters,
+            residuals,
+            loss_history,
+        });
+    }
+}
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// name -> (value, grad)
+fn parameters(exec: &Executor, s: &mut Store) -> Result<HashMap<String, (f32, f32)>> {
+    let mut res = HashMap::new();
+    for parameter in exec.get_parameters(s) {
+        let (name, float) = parameter?;
+        res.insert(name, (float.value, float.grad));
+    }
+    return Ok(res);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::NodeAllocator;
+    use crate::lbfgs::LBFGS;
+    use crate::optimizer::{Optimizer, SGD};
+    use crate::polynomial::tranform_system_to_graph;
+    fn executor(polys: &[Polynomial], optimizer: Optimizer, s: &mut Store) -> Executor {
+        let mut allocator = NodeAllocator::new();
+        let mut name_record = HashMap::new();
+        let graph = tranform_system_to_graph(polys, &mut name_record, &mut allocator);
+        let mut exec = Executor::new(graph, optimizer);
+        let init = HashMap::from([("x".to_string(), 4.0), ("y".to_string(), 1.0)]);
+        exec.init_parameter(&init, s).unwrap();
+        exec.init_contants(s);
+        return exec;
+    }
+    #[test]
+    fn stopping_criteria() {
+        let polys = vec![
+            Polynomial::parse("x + y - 3").unwrap(),
+            Polynomial::parse("x - y - 1").unwrap(),
+        ];
+        let mut s = Store::new();
+        let mut exec = executor(&polys, Optimizer::LBFGS(LBFGS::new(8)), &mut s);
+        let report = Solver::new().solve(&mut exec, &mut s, &polys).unwrap();
+        assert_eq!(report.termination, Termination::LossTolerance);
+        assert!(report.iterations < 20);
+        assert_eq!(report.loss_history.len(), report.iterations + 1);
+        assert!((report.parameters["x"] - 2.0).abs() < 1e-4);
+        assert!(report.residuals.iter().all(|r| r.abs() < 1e-4));
+        // a tiny learning rate never gets there
+        let mut s = Store::new();
+        let mut exec = executor(&polys, Optimizer::SGD(SGD::new(1e-4)), &mut s);
+        let report = Solver::new()
+            .with_max_iterations(5)
+            .solve(&mut exec, &mut s, &polys)
+            .unwrap();
+        assert_eq!(report.termination, Termination::MaxIterations);
+        assert_eq!(report.iterations, 5);
+        let mut s = Store::new();
+        let mut exec = executor(&polys, Optimizer::SGD(SGD::new(1e-4)), &mut s);
+        let report = Solver::new()
+            .with_parameter_tolerance(1e-2)
+            .solve(&mut exec, &mut s, &polys)
+            .unwrap();
+        assert_eq!(report.termination, Termination::ParameterTolerance);
+        assert_eq!(report.iterations, 1);
+        // x^2 + 1 has a minimum but no root
+        let polys = vec![Polynomial::parse("x^2 + 1").unwrap()];
+        let mut s = Store::new();
+        let mut exec = executor(&polys, Optimizer::SGD(SGD::new(0.01)), &mut s);
+        let report = Solver::new()
+            .with_gradient_tolerance(1e-3)
+            .with_max_iterations(1000)
+            .solve(&mut exec, &mut s, &polys)
+            .unwrap();
+        assert_eq!(report.termination, Termination::GradientTolerance);
+        assert!((report.final_loss() - 1.0).abs() < 1e-3);
+    }
+    #[test]
+    fn line_search_failure() {
+        // from (1, 1) the iterates stay on x = y and end at the saddle x = y = sqrt(1.8),
+        // loss 0.8, where no step decreases the loss. that must not read as converged
+        let polys = vec![
+            Polynomial::parse("x^2 + y^2 - 4").unwrap(),
+            Polynomial::parse("x y - 1").unwrap(),
+        ];
+        let mut s = Store::new();
+        let mut exec = executor(&polys, Optimizer::LBFGS(LBFGS::new(8)), &mut s);
+        let init = HashMap::from([("x".to_string(), 1.0), ("y".to_string(), 1.0)]);
+        exec.init_parameter(&init, &mut s).unwrap();
+        let report = Solver::new().solve(&mut exec, &mut s, &polys).unwrap();
+        assert_eq!(report.termination, Termination::LineSearchFailed);
+        assert!((report.final_loss() - 0.8).abs() < 1e-3);
+        assert!((report.parameters["x"] - 1.8f32.sqrt()).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,221 @@
+use crate::division::{cut, derivative, div_rem, trim};
+use crate::error::{FFError, Result};
+use crate::polynomial::{horner, Polynomial};
+
+// remainders below this (relative to the largest coefficient) end the sequence
+const REMAINDER_TOLERANCE: f64 = 1e-12;
+// bisection stops splitting intervals narrower than this (relative to the root bound)
+const MIN_WIDTH: f64 = 1e-12;
+// root refinement stops once the bracket is this narrow (relative to max(1, |root|))
+const ROOT_TOLERANCE: f64 = 1e-14;
+const MAX_REFINE_ITERATIONS: usize = 200;
+
+// p, p', -rem(p, p'), ... in f64, coefficients lowest degree first
+#[derive(Debug, Clone)]
+pub struct SturmSequence {
+    polys: Vec<Vec<f64>>,
+}
+impl SturmSequence {
+    pub fn new(coefficients: &[f64]) -> Result<Self> {
+        let p = trim(coefficients);
+        if p.iter().all(|c| *c == 0.0) {
+            return Err(FFError::Polynomial(
+                "the zero polynomial has no Sturm sequence".to_string(),
+            ));
+        }
+        let mut polys = vec![p];
+        if polys[0].len() > 1 {
+            polys.push(derivative(&polys[0]));
+        }
+        while polys[polys.len() - 1].len() > 1 {
+            let n = polys.len();
+            let r = remainder(&polys[n - 2], &polys[n - 1]);
+            if r.iter().all(|c| *c == 0.0) {
+                break;
+            }
+            polys.push(r.iter().map(|c| -c).collect());
+        }
+        return Ok(SturmSequence { polys });
+    }
+    pub fn get_polynomials(&self) -> &[Vec<f64>] {
+        return &self.polys;
+    }
+    // sign changes of the sequence at x, zeros are skipped
+    pub fn sign_changes(&self, x: f64) -> usize {
+        let mut changes = 0;
+        let mut last = 0.0;
+        for p in self.polys.iter() {
+            let value = horner(p, x);
+            if value == 0.0 {
+                continue;
+            }
+            if last * value < 0.0 {
+                changes += 1;
+            }
+            last = value;
+        }
+        return changes;
+    }
+    // number of distinct real roots in (a, b]
+    pub fn count_roots(&self, a: f64, b: f64) -> usize {
+        if a >= b {
+            return 0;
+        }
+        return self.sign_changes(a).saturating_sub(self.sign_changes(b));
+    }
+    // Cauchy bound, every real root lies in [-bound, bound]
+    pub fn root_bound(&self) -> f64 {
+        let p = &self.polys[0];
+        let n = p.len() - 1;
+        return 1.0 + p[..n].iter().map(|c| (c / p[n]).abs()).fold(0.0, f64::max);
+    }
+    // disjoint intervals (lo, hi] inside (a, b] holding exactly one distinct real root each,
+    // in increasing order
+    pub fn isolate(&self, a: f64, b: f64) -> Vec<(f64, f64)> {
+        let min_width = MIN_WIDTH * self.root_bound();
+        let mut intervals = Vec::new();
+        let mut pending = vec![(a, b, self.sign_changes(a), self.sign_changes(b))];
+        while let Some((lo, hi, v_lo, v_hi)) = pending.pop() {
+            let count = v_lo.saturating_sub(v_hi);
+            if count == 0 {
+                continue;
+            }
+            // roots closer than the working precision cannot be separated further
+            if count == 1 || hi - lo <= min_width {
+                intervals.push((lo, hi));
+                continue;
+            }
+            let mid = 0.5 * (lo + hi);
+            let v_mid = self.sign_changes(mid);
+            pending.push((mid, hi, v_mid, v_hi));
+            pending.push((lo, mid, v_lo, v_mid));
+        }
+        intervals.sort_by(|x, y| x.0.total_cmp(&y.0));
+        return intervals;
+    }
+    // the root inside an isolating interval (lo, hi], by the Illinois variant of regula
+    // falsi on the square-free part p / gcd(p, p'), which changes sign at every root
+    pub fn refine(&self, lo: f64, hi: f64) -> Result<f64> {
+        let gcd = &self.polys[self.polys.len() - 1];
+        let f = match gcd.len() {
+            1 => self.polys[0].clone(),
+            _ => div_rem(&self.polys[0], gcd)?.0,
+        };
+        let (mut a, mut b) = (lo, hi);
+        let (mut fa, mut fb) = (horner(&f, a), horner(&f, b));
+        if fb == 0.0 {
+            return Ok(b);
+        }
+        if fa * fb > 0.0 {
+            return Err(FFError::Polynomial(format!(
+                "no sign change in ({}, {}], not an isolating interval",
+                lo, hi
+            )));
+        }
+        // which end was kept last, -1 for a, 1 for b
+        let mut side = 0;
+        for _ in 0..MAX_REFINE_ITERATIONS {
+            let x = (a * fb - b * fa) / (fb - fa);
+            // fall back to bisection when the secant leaves the bracket
+            let x = if x > a && x < b { x } else { 0.5 * (a + b) };
+            let fx = horner(&f, x);
+            if fx == 0.0 || b - a <= ROOT_TOLERANCE * x.abs().max(1.0) {
+                return Ok(x);
+            }
+            if fa * fx < 0.0 {
+                b = x;
+                fb = fx;
+                if side == -1 {
+                    fa *= 0.5;
+                }
+                side = -1;
+            } else {
+                a = x;
+                fa = fx;
+                if side == 1 {
+                    fb *= 0.5;
+                }
+                side = 1;
+            }
+        }
+        return Err(FFError::Polynomial(format!(
+            "root in ({}, {}] did not converge within {} iterations, last bracket ({}, {})",
+            lo, hi, MAX_REFINE_ITERATIONS, a, b
+        )));
+    }
+}
+
+impl Polynomial {
+    pub fn sturm_sequence(&self) -> Result<SturmSequence> {
+        return SturmSequence::new(&self.univariate()?);
+    }
+    // number of distinct real roots in (a, b]
+    pub fn count_real_roots(&self, a: f64, b: f64) -> Result<usize> {
+        return Ok(self.sturm_sequence()?.count_roots(a, b));
+    }
+    // isolating intervals for every distinct real root
+    pub fn isolate_real_roots(&self) -> Result<Vec<(f64, f64)>> {
+        let sturm = self.sturm_sequence()?;
+        let bound = sturm.root_bound();
+        return Ok(sturm.isolate(-bound, bound));
+    }
+    // the root inside an isolating interval, see `SturmSequence::refine`
+    pub fn refine_root(&self, interval: (f64, f64)) -> Result<f64> {
+        return self.sturm_sequence()?.refine(interval.0, interval.1);
+    }
+}
+
+// remainder of p / q with coefficients that are negligible relative to p cut to zero
+fn remainder(p: &[f64], q: &[f64]) -> Vec<f64> {
+    let scale = p.iter().fold(0.0, |acc: f64, c| acc.max(c.abs()));
+    let (_, r) = div_rem(p, q).unwrap();
+    return cut(&r, REMAINDER_TOLERANCE * scale);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::product;
+    #[test]
+    fn count() {
+        let p = product(&["x - 1", "x + 2", "x - 3", "x^2 + 1"]);
+        assert_eq!(p.count_real_roots(-10.0, 10.0).unwrap(), 3);
+        assert_eq!(p.count_real_roots(0.0, 2.0).unwrap(), 1);
+        assert_eq!(p.count_real_roots(1.0, 3.0).unwrap(), 1);
+        assert_eq!(p.count_real_roots(-1.0, 0.5).unwrap(), 0);
+        // distinct roots only
+        let q = product(&["x - 1", "x - 1", "x + 1"]);
+        assert_eq!(q.count_real_roots(-5.0, 5.0).unwrap(), 2);
+    }
+    #[test]
+    fn isolate_and_refine() {
+        // roots that are exact in f32, so are the coefficients
+        let p = product(&["x - 1", "x - 1.25", "x + 2", "x^2 + 1"]);
+        let intervals = p.isolate_real_roots().unwrap();
+        assert_eq!(intervals.len(), 3);
+        let sturm = p.sturm_sequence().unwrap();
+        for (i, (lo, hi)) in intervals.iter().enumerate() {
+            assert_eq!(sturm.count_roots(*lo, *hi), 1);
+            if i > 0 {
+                assert!(intervals[i - 1].1 <= *lo);
+            }
+        }
+        let roots = intervals
+            .iter()
+            .map(|interval| p.refine_root(*interval).unwrap())
+            .collect::<Vec<f64>>();
+        for (root, expected) in roots.iter().zip([-2.0, 1.0, 1.25]) {
+            assert!((root - expected).abs() < 1e-9, "{} != {}", root, expected);
+        }
+        // a double root does not change the sign of p itself
+        let q = product(&["x - 1", "x - 1", "x + 3"]);
+        let roots = q
+            .isolate_real_roots()
+            .unwrap()
+            .iter()
+            .map(|interval| q.refine_root(*interval).unwrap())
+            .collect::<Vec<f64>>();
+        assert_eq!(roots.len(), 2);
+        assert!((roots[0] + 3.0).abs() < 1e-9 && (roots[1] - 1.0).abs() < 1e-9);
+        assert!(p.refine_root((1.5, 1.8)).is_err());
+    }
+}
@@ -0,0 +1,16 @@
+// polynomial builders shared by the unit tests
+use crate::polynomial::Polynomial;
+
+// the product of the parsed factors, for polynomials with known roots
+pub fn product(factors: &[&str]) -> Polynomial {
+    return factors.iter().fold(Polynomial::constant(1.0), |acc, f| {
+        &acc * &Polynomial::parse(f).unwrap()
+    });
+}
+// one parsed polynomial per equation
+pub fn system(equations: &[&str]) -> Vec<Polynomial> {
+    return equations
+        .iter()
+        .map(|e| Polynomial::parse(e).unwrap())
+        .collect();
+}